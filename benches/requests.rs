@@ -0,0 +1,15 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use sonoff_minir3::bench::{switches_body, switches_body_serialized};
+use sonoff_minir3::SwitchPosition;
+
+fn switches_request(c: &mut Criterion) {
+    c.bench_function("switches body", |b| {
+        b.iter(|| switches_body(black_box(SwitchPosition::On)))
+    });
+    c.bench_function("switches body serialized", |b| {
+        b.iter(|| switches_body_serialized(black_box(SwitchPosition::On)))
+    });
+}
+
+criterion_group!(benches, switches_request);
+criterion_main!(benches);
@@ -0,0 +1,436 @@
+//! A blocking client for Sonoff mini R3 API.
+//!
+//! It mirrors core API of async `Client` for contexts without async runtime, e.g. small CLI
+//! tools. Available only with `blocking` feature.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use sonoff_minir3::blocking::Client;
+//!
+//! let client = Client::new("192.168.1.75", 8081);
+//!
+//! client.set_switch_position(SwitchPosition::On)?;
+//! ```
+use crate::client::{check_switch_position, timeout_error, zeroconf_url, INFO_REQUEST_BODY};
+use crate::models::*;
+use crate::Result;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+use std::time::Duration;
+
+/// A blocking client for Sonoff mini R3 API, look at async `Client` for details on its methods.
+///
+/// Blocking client shouldn't be used within async runtime, since it would block it.
+#[derive(Clone, Debug)]
+pub struct Client {
+    host: String,
+    port: u16,
+    scheme: Scheme,
+    lenient: bool,
+    default_outlet: OutletIndex,
+    timeout: Option<Duration>,
+    inner: reqwest::blocking::Client,
+}
+
+impl Client {
+    /// Constructs a new `Client` with given host and port
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use sonoff_minir3::blocking::Client;
+    ///
+    /// let client = Client::new("192.168.1.75", 8081);
+    /// ```
+    pub fn new<H: Into<String>>(host: H, port: u16) -> Self {
+        Self::with_http_client(host, port, reqwest::blocking::Client::new())
+    }
+
+    /// Constructs a new `Client` with given host, port and request timeout
+    ///
+    /// Requests that didn't complete in time fail with `Error::TimedOut`.
+    pub fn with_timeout<H: Into<String>>(host: H, port: u16, timeout: Duration) -> Self {
+        let inner = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap();
+        Client {
+            timeout: Some(timeout),
+            ..Self::with_http_client(host, port, inner)
+        }
+    }
+
+    /// Constructs a new `Client` with given host and port, sending requests with given HTTP client
+    pub fn with_http_client<H: Into<String>>(
+        host: H,
+        port: u16,
+        inner: reqwest::blocking::Client,
+    ) -> Self {
+        Client {
+            host: host.into(),
+            port,
+            scheme: Scheme::default(),
+            lenient: false,
+            default_outlet: DEFAULT_OUTLET,
+            timeout: None,
+            inner,
+        }
+    }
+
+    /// Set URL scheme, look at `ClientBuilder::scheme` for details.
+    pub fn scheme(mut self, scheme: Scheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Accept unknown switch positions, look at `ClientBuilder::lenient` for details.
+    pub fn lenient(mut self, enabled: bool) -> Self {
+        self.lenient = enabled;
+        self
+    }
+
+    /// Set outlet used by single-outlet methods, look at `ClientBuilder::default_outlet` for
+    /// details.
+    pub fn default_outlet(mut self, outlet: OutletIndex) -> Self {
+        self.default_outlet = outlet;
+        self
+    }
+
+    fn post(&self, path: &str, body: Cow<'static, [u8]>) -> Result<Response> {
+        let url = zeroconf_url(self.scheme, &self.host, self.port, path);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%url, "sending request");
+
+        self.inner
+            .post(url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.into_owned())
+            .send()
+            .map_err(|e| timeout_error(e, self.timeout, None))
+    }
+
+    /// Fetch device info, look at `Client::fetch_info` for details.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlet = self.default_outlet.get())))]
+    pub fn fetch_info(&self) -> Result<Info> {
+        let response = self.post("info", Cow::Borrowed(INFO_REQUEST_BODY))?;
+        let info: RawInfo = read_json::<InfoResponse>(response)?.try_into()?;
+        let info = Info::for_outlet(info, self.default_outlet.get())?;
+
+        check_switch_position(&info.switch, self.lenient)?;
+        Ok(info)
+    }
+
+    /// Fetch all reported device info, look at `Client::fetch_raw_info` for details.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host)))]
+    pub fn fetch_raw_info(&self) -> Result<RawInfo> {
+        let response = self.post("info", Cow::Borrowed(INFO_REQUEST_BODY))?;
+        let info: RawInfo = read_json::<InfoResponse>(response)?.try_into()?;
+
+        for s in &info.switches {
+            check_switch_position(&s.switch, self.lenient)?;
+        }
+        Ok(info)
+    }
+
+    /// Set startup position for device, look at `Client::set_startup_position` for details.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlet = self.default_outlet.get(), ?position)))]
+    pub fn set_startup_position(&self, position: StartupPosition) -> Result<()> {
+        let response = self.post(
+            "startups",
+            Cow::Owned(serde_json::to_vec(&StartupsRequest::for_positions(&[(
+                self.default_outlet,
+                position,
+            )]))?),
+        )?;
+        parse_empty_response(response)
+    }
+
+    /// Set current switch position, look at `Client::set_switch_position` for details.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlet = self.default_outlet.get())))]
+    pub fn set_switch_position(&self, position: impl Into<SwitchPosition>) -> Result<()> {
+        let position = position.into();
+        let body = match self.default_outlet {
+            FIRST_OUTLET => SwitchesRequest::body(position.clone()),
+            v => Cow::Owned(serde_json::to_vec(&SwitchesRequest::for_positions(&[(
+                v,
+                position.clone(),
+            )]))?),
+        };
+        let mut response = self.post("switches", body)?;
+
+        // Older single-channel firmware supports only singular API
+        if self.default_outlet == FIRST_OUTLET && response.status() == StatusCode::NOT_FOUND {
+            response = self.post(
+                "switch",
+                Cow::Owned(serde_json::to_vec(&SwitchRequest::from(position))?),
+            )?;
+        }
+        parse_empty_response(response)
+    }
+
+    /// Switch default outlet on, same as `set_switch_position(SwitchPosition::On)`.
+    pub fn turn_on(&self) -> Result<()> {
+        self.set_switch_position(SwitchPosition::On)
+    }
+
+    /// Switch default outlet off, same as `set_switch_position(SwitchPosition::Off)`.
+    pub fn turn_off(&self) -> Result<()> {
+        self.set_switch_position(SwitchPosition::Off)
+    }
+}
+
+type Response = reqwest::blocking::Response;
+
+fn parse_empty_response(response: Response) -> Result<()> {
+    read_json::<EmptyResponse>(response)?.try_into()
+}
+
+fn read_json<T: DeserializeOwned>(response: Response) -> Result<T> {
+    check_status(response.status())?;
+    let body = response.bytes()?;
+    parse_json(&body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use httpmock::MockServer;
+
+    fn make_server_and_client() -> (MockServer, Client) {
+        let server = MockServer::start();
+        let client = Client::new(server.host(), server.port());
+        (server, client)
+    }
+
+    mod fetch_info {
+        use super::*;
+
+        #[test]
+        fn returns_expected_result() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/info")
+                    .body("{\"data\":{}}");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_ok.json"));
+            });
+
+            let got = client.fetch_info();
+
+            mock.assert();
+
+            assert_eq!(got.unwrap(), info())
+        }
+
+        #[test]
+        fn errored_in_expected_way() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_error.json"));
+            });
+
+            let got = client.fetch_info();
+
+            assert_eq!(got.unwrap_err(), Error::WrongParameters(None))
+        }
+
+        #[test]
+        fn errored_on_server_error_status() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(500);
+            });
+
+            let got = client.fetch_info();
+
+            assert_eq!(got.unwrap_err(), Error::HttpStatus(500))
+        }
+
+        #[test]
+        fn returns_unknown_switch_position_if_lenient() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_unknown_switch.json"));
+            });
+
+            let got = client.lenient(true).fetch_info();
+
+            assert_eq!(
+                got.unwrap().switch,
+                SwitchPosition::Unknown("unavailable".to_string())
+            )
+        }
+
+        #[test]
+        fn read_default_outlet() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_mixed_outlets.json"));
+            });
+
+            let got = client
+                .default_outlet(OutletIndex::new(2).unwrap())
+                .fetch_info()
+                .unwrap();
+
+            assert_eq!(got.switch, SwitchPosition::On);
+            assert_eq!(got.startup, StartupPosition::On);
+        }
+
+        #[test]
+        fn errored_on_timeout() {
+            let server = MockServer::start();
+            let client =
+                Client::with_timeout(server.host(), server.port(), Duration::from_millis(50));
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .delay(Duration::from_millis(500))
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_ok.json"));
+            });
+
+            let got = client.fetch_info();
+
+            assert_eq!(got.unwrap_err(), Error::TimedOut(Duration::from_millis(50)))
+        }
+    }
+
+    mod fetch_raw_info {
+        use super::*;
+
+        #[test]
+        fn returns_expected_result() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_ok.json"));
+            });
+
+            let got = client.fetch_raw_info();
+
+            assert_eq!(got.unwrap().fw_version, Some("1.4.3".to_string()))
+        }
+    }
+
+    mod set_startup_position {
+        use super::*;
+
+        #[test]
+        fn sent_expected_request() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/startups")
+                    .body(load_fixture("request_startups_ok.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.set_startup_position(StartupPosition::Stay);
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+    }
+
+    mod set_switch_position {
+        use super::*;
+
+        #[test]
+        fn sent_expected_request() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/switches")
+                    .body(load_fixture("request_switches_ok.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.set_switch_position(SwitchPosition::On);
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[test]
+        fn fell_back_to_switch_endpoint() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/switches");
+                then.status(404);
+            });
+            let mock = server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/switch");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.turn_on();
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[test]
+        fn errored_in_expected_way() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/switches");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_error.json"));
+            });
+
+            let got = client.turn_off();
+
+            assert_eq!(got.unwrap_err(), Error::WrongParameters(None))
+        }
+
+        #[test]
+        fn sent_for_default_outlet() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/switches")
+                    .body(load_fixture("request_switches_outlet_2.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client
+                .default_outlet(OutletIndex::new(2).unwrap())
+                .set_switch_position(SwitchPosition::Off);
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+    }
+}
@@ -1,9 +1,438 @@
 use crate::models::*;
+use crate::reaper::Pool;
+#[cfg(feature = "tokio")]
+use crate::reaper::Reaper;
+use crate::recorder::Recorder;
+use crate::runtime;
+#[cfg(feature = "tokio")]
+use crate::tasks::Tasks;
+use crate::Result;
+use flate2::write::GzEncoder;
+use futures_util::future::join_all;
+use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::redirect::{Attempt, Policy};
+use reqwest::{Response, StatusCode};
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+use std::fmt;
+use std::future::Future;
+use std::io::{self, Write};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+#[cfg(feature = "ota")]
+use std::time::Instant;
+use std::time::{Duration, SystemTime};
 
+pub(crate) const INFO_REQUEST_BODY: &[u8] = b"{\"data\":{}}";
+const DIY_AP_HOST: &str = "10.10.7.1";
+const DIY_AP_PORT: u16 = 8081;
+const DEFAULT_PORT: u16 = 8081;
+const DIY_AP_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(500);
+#[cfg(feature = "ota")]
+const VERIFY_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_REDIRECTS: usize = 10;
+const REDACTED: &str = "<redacted>";
+
+/// Header used to address a device behind a shared gateway.
+pub const DEVICE_ID_HEADER: &str = "X-Device-Id";
+
+#[derive(Clone)]
+enum Auth {
+    Basic { user: String, password: String },
+    Bearer(String),
+}
+
+#[derive(Clone)]
 pub struct Client {
     host: String,
     port: u16,
-    inner: reqwest::Client,
+    scheme: Scheme,
+    inner: Arc<Pool>,
+    lenient: bool,
+    device_id: Option<String>,
+    auth: Option<Auth>,
+    compress_requests: bool,
+    last_command: Arc<Mutex<Option<CommandRecord>>>,
+    capabilities: Arc<Mutex<Option<Capabilities>>>,
+    retries: usize,
+    retry_delay: Duration,
+    timeout: Arc<RwLock<Option<Duration>>>,
+    connect_timeout: Option<Duration>,
+    queue: Option<Arc<runtime::Mutex<()>>>,
+    default_outlets: Vec<OutletIndex>,
+    default_outlet: OutletIndex,
+    pulse_width_unit: PulseWidthUnit,
+    recorder: Option<Arc<Recorder>>,
+    // Last sequence sent, shared between clones so they never reuse it
+    sequence: Option<Arc<AtomicU64>>,
+    #[cfg(feature = "tokio")]
+    pub(crate) tasks: Arc<Tasks>,
+    // Only held to stop reaper once all clones of client are dropped
+    #[cfg(feature = "tokio")]
+    _reaper: Option<Arc<Reaper>>,
+}
+
+/// Constructs a new `Client` with IP and port of given address.
+///
+/// Port 0 and scoped IPv6 addresses are rejected with `Error::InvalidParameter`.
+impl TryFrom<SocketAddr> for Client {
+    type Error = Error;
+
+    fn try_from(value: SocketAddr) -> Result<Self> {
+        let host = match value {
+            SocketAddr::V6(v) if v.scope_id() != 0 => format!("{}%{}", v.ip(), v.scope_id()),
+            v => v.ip().to_string(),
+        };
+        ClientBuilder::new(host, value.port()).build()
+    }
+}
+
+/// Only host and port are printed, since the rest are internals or secrets like credentials.
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A builder to construct `Client` with non-default options.
+///
+/// # Example
+///
+/// ```
+/// # use sonoff_minir3::ClientBuilder;
+///
+/// let client = ClientBuilder::new("192.168.1.75", 8081)
+///     .lenient(true)
+///     .build()
+///     .unwrap();
+/// ```
+pub struct ClientBuilder {
+    host: String,
+    port: u16,
+    lenient: bool,
+    device_id: Option<String>,
+    auth: Option<Auth>,
+    compress_requests: bool,
+    retries: usize,
+    retry_delay: Duration,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    redirect_policy: Option<Arc<Policy>>,
+    http_client: Option<reqwest::Client>,
+    #[cfg(feature = "tokio")]
+    reap_idle_connections: Option<Duration>,
+    serialize_requests: bool,
+    default_outlets: Vec<OutletIndex>,
+    default_outlet: Option<u8>,
+    pulse_width_unit: PulseWidthUnit,
+    record_to: Option<PathBuf>,
+    sequence_commands: bool,
+    scheme: Scheme,
+}
+
+impl ClientBuilder {
+    /// Constructs a new `ClientBuilder` with given host and port
+    pub fn new<H: Into<String>>(host: H, port: u16) -> Self {
+        ClientBuilder {
+            host: host.into(),
+            port,
+            lenient: false,
+            device_id: None,
+            auth: None,
+            compress_requests: false,
+            retries: 0,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            timeout: None,
+            connect_timeout: None,
+            redirect_policy: None,
+            http_client: None,
+            #[cfg(feature = "tokio")]
+            reap_idle_connections: None,
+            serialize_requests: false,
+            default_outlets: vec![DEFAULT_OUTLET],
+            default_outlet: None,
+            pulse_width_unit: PulseWidthUnit::default(),
+            record_to: None,
+            sequence_commands: false,
+            scheme: Scheme::default(),
+        }
+    }
+
+    /// Allow device to report values unknown to this crate.
+    ///
+    /// By default unexpected switch position fails whole info fetch with
+    /// `Error::UnexpectedSwitchPosition`. Lenient client returns `SwitchPosition::Unknown`
+    /// instead.
+    pub fn lenient(mut self, enabled: bool) -> Self {
+        self.lenient = enabled;
+        self
+    }
+
+    /// Target specific device on an endpoint shared by several devices.
+    ///
+    /// Given id is sent in `X-Device-Id` header with every request, so gateway could route it to
+    /// the right device.
+    pub fn target_device<D: Into<String>>(mut self, device_id: D) -> Self {
+        self.device_id = Some(device_id.into());
+        self
+    }
+
+    /// Send HTTP Basic credentials in `Authorization` header with every request.
+    ///
+    /// Devices themselves don't check it, so this is useful only for authenticating reverse
+    /// proxies. Overrides `bearer_auth`.
+    pub fn basic_auth<U: Into<String>, P: Into<String>>(mut self, user: U, password: P) -> Self {
+        self.auth = Some(Auth::Basic {
+            user: user.into(),
+            password: password.into(),
+        });
+        self
+    }
+
+    /// Send bearer token in `Authorization` header with every request.
+    ///
+    /// Same as for `basic_auth`, it's useful only for authenticating reverse proxies. Overrides
+    /// `basic_auth`.
+    pub fn bearer_auth<T: Into<String>>(mut self, token: T) -> Self {
+        self.auth = Some(Auth::Bearer(token.into()));
+        self
+    }
+
+    /// Compress request bodies with gzip.
+    ///
+    /// Devices themselves don't expect compressed requests, so this is useful only for gateways
+    /// requiring it.
+    pub fn compress_requests(mut self, enabled: bool) -> Self {
+        self.compress_requests = enabled;
+        self
+    }
+
+    /// Retry failed requests up to `retries` times, disabled by default.
+    ///
+    /// Requests are retried if they failed to reach device, timed out or device responded that
+    /// it's busy (HTTP 429 or 503). API errors are never retried, since device would report them
+    /// again. Delay suggested by device in `Retry-After` header is respected, otherwise delay
+    /// is doubled with every attempt starting from `retry_delay`.
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Delay before the first retry, 500ms by default.
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Use given host instead of one builder was constructed with.
+    pub fn host<H: Into<String>>(mut self, host: H) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Use given port instead of one builder was constructed with.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Limit time of every request, unlimited by default.
+    ///
+    /// Requests that didn't complete in time fail with `Error::TimedOut`. Timeout could be changed
+    /// later with `Client::set_timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Limit time to establish connection to device, unlimited by default.
+    ///
+    /// Connections that weren't established in time fail with `Error::TimedOut` holding this
+    /// timeout rather than the request one.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Use given policy for redirects, e.g. issued by reverse proxy.
+    ///
+    /// By default up to 10 redirects are followed, but only 307 and 308 ones, since they
+    /// preserve method and body. Other redirects turn commands into GET requests, so they aren't
+    /// followed and make request fail instead.
+    pub fn redirect_policy(mut self, policy: Policy) -> Self {
+        self.redirect_policy = Some(Arc::new(policy));
+        self
+    }
+
+    /// Send requests with given HTTP client, e.g. to share its connection pool or proxy settings
+    /// between clients of several devices.
+    ///
+    /// Connect timeout and redirect policy should be configured on given client, ones set on
+    /// builder are ignored. Reaping doesn't close its idle connections, since they are shared.
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Periodically drop connections that stayed idle for longer than `max_idle`.
+    ///
+    /// Reaper runs as a background task, so client should be built within tokio runtime. Task is
+    /// stopped once client and all its clones are dropped. Available only with `tokio` feature.
+    ///
+    /// Idle connections consume file descriptors, which may become a problem when one service
+    /// holds clients for thousands of devices. On the other hand, reaped connections have to be
+    /// established again on the next request, which adds latency to it. So `max_idle` should be
+    /// much longer than a typical interval between requests to the same device.
+    #[cfg(feature = "tokio")]
+    pub fn reap_idle_connections(mut self, max_idle: Duration) -> Self {
+        self.reap_idle_connections = Some(max_idle);
+        self
+    }
+
+    /// Send requests to device one by one in order they were issued, disabled by default.
+    ///
+    /// Requests issued concurrently (also via clones of client) wait for previous ones to
+    /// complete, including their retries. This prevents interleaving of commands and overloading
+    /// device with concurrent requests. Order of requests is preserved only with `tokio` feature.
+    pub fn serialize_requests(mut self, enabled: bool) -> Self {
+        self.serialize_requests = enabled;
+        self
+    }
+
+    /// Outlets controlled by switch commands, outlet 0 by default.
+    ///
+    /// `set_switch_position`, `turn_on` and `turn_off` set position of all given outlets in one
+    /// request. Empty set isn't allowed.
+    pub fn default_outlets(mut self, outlets: &[OutletIndex]) -> Self {
+        self.default_outlets = outlets.to_vec();
+        self
+    }
+
+    /// Outlet used by single-outlet methods, outlet 0 by default.
+    ///
+    /// `fetch_info` reads given outlet and `set_startup_position` sets it, it also replaces
+    /// `default_outlets` with given one. Outlet should be within `0..=3`, otherwise `build` fails
+    /// with `Error::InvalidParameter`.
+    pub fn default_outlet(mut self, outlet: u8) -> Self {
+        self.default_outlet = Some(outlet);
+        self
+    }
+
+    /// Unit device reports pulse width in, milliseconds by default.
+    ///
+    /// Some firmware reports it in seconds, so it's used by `fetch_pulse_width` to convert
+    /// reported value.
+    pub fn pulse_width_unit(mut self, unit: PulseWidthUnit) -> Self {
+        self.pulse_width_unit = unit;
+        self
+    }
+
+    /// URL scheme requests are sent with, `Scheme::Http` by default.
+    ///
+    /// Devices accept only plain HTTP, so `Scheme::Https` is useful only for devices behind HTTPS
+    /// reverse proxy.
+    pub fn scheme(mut self, scheme: Scheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Record every request and response to JSONL file at given path.
+    ///
+    /// Each line is an object with `endpoint`, `request`, `response` and `timestamp` (milliseconds
+    /// since unix epoch) fields. Bodies are recorded as they were sent and received. File is
+    /// appended to if it already exists. Recording is best-effort, so failed writes don't fail
+    /// requests.
+    pub fn record_to<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.record_to = Some(path.into());
+        self
+    }
+
+    /// Send unique `sequence` with every command, disabled by default.
+    ///
+    /// Sequence is a unix timestamp in milliseconds, bumped if needed so it's never reused by
+    /// client and its clones. Retries of the same command reuse its sequence, so device supporting
+    /// it applies command only once, even if its response was lost. Other devices ignore it.
+    pub fn sequence_commands(mut self, enabled: bool) -> Self {
+        self.sequence_commands = enabled;
+        self
+    }
+
+    /// Constructs `Client` with configured options
+    ///
+    /// Port 0, empty default outlets and out of range default outlet are rejected with
+    /// `Error::InvalidParameter`.
+    pub fn build(mut self) -> Result<Client> {
+        if self.port == 0 {
+            return Err(Error::InvalidParameter("port must not be 0".to_string()));
+        }
+        check_unscoped(&self.host)?;
+        let default_outlet = match self.default_outlet {
+            Some(v) => {
+                let outlet = OutletIndex::new(v)?;
+                self.default_outlets = vec![outlet];
+                outlet
+            }
+            None => DEFAULT_OUTLET,
+        };
+        if self.default_outlets.is_empty() {
+            return Err(Error::InvalidParameter(
+                "default outlets must not be empty".to_string(),
+            ));
+        }
+
+        let connect_timeout = self.connect_timeout;
+        let redirect_policy = self.redirect_policy.clone();
+        let inner = Arc::new(Pool::new(match self.http_client.clone() {
+            Some(v) => Box::new(move || Ok(v.clone())),
+            None => Box::new(move || http_client(connect_timeout, redirect_policy.clone())),
+        })?);
+        let recorder = match &self.record_to {
+            Some(v) => Some(Arc::new(Recorder::open(v)?)),
+            None => None,
+        };
+        #[cfg(feature = "tokio")]
+        let tasks = Arc::new(Tasks::default());
+        #[cfg(feature = "tokio")]
+        let reaper = match self.reap_idle_connections {
+            Some(v) => Some(Arc::new(Reaper::spawn(&tasks, inner.clone(), v)?)),
+            None => None,
+        };
+
+        Ok(Client {
+            host: self.host,
+            port: self.port,
+            scheme: self.scheme,
+            inner,
+            lenient: self.lenient,
+            device_id: self.device_id,
+            auth: self.auth,
+            compress_requests: self.compress_requests,
+            last_command: Arc::default(),
+            capabilities: Arc::default(),
+            retries: self.retries,
+            retry_delay: self.retry_delay,
+            timeout: Arc::new(RwLock::new(self.timeout)),
+            connect_timeout,
+            queue: self
+                .serialize_requests
+                .then(|| Arc::new(runtime::Mutex::new(()))),
+            default_outlets: self.default_outlets,
+            default_outlet,
+            pulse_width_unit: self.pulse_width_unit,
+            recorder,
+            sequence: self.sequence_commands.then(|| Arc::new(AtomicU64::new(0))),
+            #[cfg(feature = "tokio")]
+            tasks,
+            #[cfg(feature = "tokio")]
+            _reaper: reaper,
+        })
+    }
 }
 
 /// An aynchronous client for Sonoff mini R3 API
@@ -20,194 +449,4938 @@ impl Client {
     ///
     /// let client = Client::new("192.168.1.75", 8081);
     /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if port is 0 or host is scoped IPv6 address, use `Client::builder` to handle it
+    /// gracefully.
     pub fn new<H: Into<String>>(host: H, port: u16) -> Self {
-        Client {
-            host: host.into(),
-            port,
-            inner: reqwest::Client::builder()
-                .http1_title_case_headers()
-                .build()
-                .unwrap(),
+        ClientBuilder::new(host, port).build().unwrap()
+    }
+
+    /// Constructs a new `Client` with given host, port and request timeout
+    ///
+    /// Requests that didn't complete in time fail with `Error::TimedOut`, so they could be
+    /// retried.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use sonoff_minir3::Client;
+    /// # use std::time::Duration;
+    ///
+    /// let client = Client::with_timeout("192.168.1.75", 8081, Duration::from_secs(5));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if port is 0, use `Client::builder` to handle it gracefully.
+    pub fn with_timeout<H: Into<String>>(host: H, port: u16, timeout: Duration) -> Self {
+        ClientBuilder::new(host, port)
+            .timeout(timeout)
+            .build()
+            .unwrap()
+    }
+
+    /// Constructs a new `Client` with given host and port, sending requests with given HTTP client
+    ///
+    /// Look at `ClientBuilder::http_client` for details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use sonoff_minir3::Client;
+    ///
+    /// let http = reqwest::Client::new();
+    /// let living_room = Client::with_http_client("192.168.1.75", 8081, http.clone());
+    /// let kitchen = Client::with_http_client("192.168.1.76", 8081, http);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if port is 0, use `Client::builder` to handle it gracefully.
+    pub fn with_http_client<H: Into<String>>(host: H, port: u16, inner: reqwest::Client) -> Self {
+        ClientBuilder::new(host, port)
+            .http_client(inner)
+            .build()
+            .unwrap()
+    }
+
+    /// Constructs a new `Client` with host and port given as single `host:port` string.
+    ///
+    /// Port could be omitted, 8081 is used then. IPv6 literals should be bracketed if port is
+    /// given, e.g. `[fe80::1]:8081`. Invalid address or port 0 are rejected with
+    /// `Error::InvalidParameter`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use sonoff_minir3::Client;
+    ///
+    /// let client = Client::from_addr("192.168.1.75:8081").unwrap();
+    /// ```
+    pub fn from_addr(addr: &str) -> Result<Self> {
+        let (host, port) = split_addr(addr)?;
+        ClientBuilder::new(host, port).build()
+    }
+
+    /// Constructs a new `Client` for device in DIY pairing mode.
+    ///
+    /// Device in pairing mode is reachable via its own access point at `10.10.7.1:8081`. Since
+    /// device is either reachable right away or not at all, client uses 2 seconds connect timeout
+    /// and no retries.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use sonoff_minir3::Client;
+    ///
+    /// let client = Client::with_defaults_for_diy_ap();
+    /// ```
+    pub fn with_defaults_for_diy_ap() -> Self {
+        ClientBuilder::new(DIY_AP_HOST, DIY_AP_PORT)
+            .connect_timeout(DIY_AP_CONNECT_TIMEOUT)
+            .retries(0)
+            .build()
+            .unwrap()
+    }
+
+    /// Constructs a new `ClientBuilder` with given host and port
+    pub fn builder<H: Into<String>>(host: H, port: u16) -> ClientBuilder {
+        ClientBuilder::new(host, port)
+    }
+
+    fn url(&self, path: &str) -> String {
+        zeroconf_url(self.scheme, &self.host, self.port, path)
+    }
+
+    fn post(&self, path: &str, body: Cow<'static, [u8]>) -> reqwest::RequestBuilder {
+        let url = self.url(path);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%url, "sending request");
+
+        let mut request = self
+            .inner
+            .get()
+            .post(url)
+            .header(CONTENT_TYPE, "application/json");
+        if let Some(v) = *self.timeout.read().unwrap() {
+            request = request.timeout(v);
+        }
+        if let Some(v) = &self.device_id {
+            request = request.header(DEVICE_ID_HEADER, v);
+        }
+        match &self.auth {
+            Some(Auth::Basic { user, password }) => {
+                request = request.basic_auth(user, Some(password))
+            }
+            Some(Auth::Bearer(token)) => request = request.bearer_auth(token),
+            None => {}
+        }
+        if self.compress_requests {
+            request.header(CONTENT_ENCODING, "gzip").body(gzip(&body))
+        } else {
+            request.body(into_body(body))
+        }
+    }
+
+    /// Stop background tasks spawned by client and all its clones, e.g. connection reaper, and
+    /// wait for them to finish.
+    ///
+    /// Tasks spawned after client is closed are stopped right away. Available only with `tokio`
+    /// feature.
+    #[cfg(feature = "tokio")]
+    pub async fn close(&self) {
+        self.tasks.close().await
+    }
+
+    /// Returns current request timeout, `None` means requests never time out.
+    pub fn timeout(&self) -> Option<Duration> {
+        *self.timeout.read().unwrap()
+    }
+
+    /// Returns how many times failed requests are retried.
+    pub fn retries(&self) -> usize {
+        self.retries
+    }
+
+    /// Returns delay before the first retry, look at `ClientBuilder::retries` for details.
+    pub fn retry_delay(&self) -> Duration {
+        self.retry_delay
+    }
+
+    /// Returns timeout to establish connection to device, `None` means it's unlimited.
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout
+    }
+
+    /// Returns effective configuration of client, e.g. to attach it to bug reports.
+    ///
+    /// Durations are given in milliseconds, `null` means unlimited. Credentials are redacted, so
+    /// it's safe to share. Requests are encrypted only if `Scheme::Https` is configured.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use sonoff_minir3::Client;
+    ///
+    /// let client = Client::new("192.168.1.75", 8081);
+    ///
+    /// assert_eq!(client.effective_config()["port"], 8081);
+    /// ```
+    pub fn effective_config(&self) -> serde_json::Value {
+        let millis = |v: Option<Duration>| v.map(|v| v.as_millis() as u64);
+        let auth = match &self.auth {
+            Some(Auth::Basic { user, .. }) => serde_json::json!({
+                "type": "basic",
+                "user": user,
+                "password": REDACTED,
+            }),
+            Some(Auth::Bearer(_)) => serde_json::json!({"type": "bearer", "token": REDACTED}),
+            None => serde_json::Value::Null,
+        };
+        serde_json::json!({
+            "host": self.host,
+            "port": self.port,
+            "scheme": self.scheme.as_str(),
+            "encrypted": self.scheme == Scheme::Https,
+            "timeout_ms": millis(self.timeout()),
+            "connect_timeout_ms": millis(self.connect_timeout),
+            "retries": self.retries,
+            "retry_delay_ms": self.retry_delay.as_millis() as u64,
+            "serialize_requests": self.queue.is_some(),
+            "default_outlets": self.default_outlets.iter().map(|v| v.get()).collect::<Vec<_>>(),
+            "default_outlet": self.default_outlet.get(),
+            "lenient": self.lenient,
+            "device_id": self.device_id,
+            "auth": auth,
+            "compress_requests": self.compress_requests,
+            "pulse_width_unit": match self.pulse_width_unit {
+                PulseWidthUnit::Milliseconds => "milliseconds",
+                PulseWidthUnit::Seconds => "seconds",
+            },
+            "record_requests": self.recorder.is_some(),
+            "sequence_commands": self.sequence.is_some(),
+        })
+    }
+
+    /// Resolve configured host and return first of its addresses.
+    ///
+    /// IP literals are returned as is without any lookup.
+    pub async fn resolved_addr(&self) -> Result<SocketAddr> {
+        resolve_addr(&self.host, self.port, runtime::lookup_host).await
+    }
+
+    /// Set request timeout, `None` disables it.
+    ///
+    /// Timeout is shared between all clones of client, so it could be changed while client is
+    /// used by other tasks. Already started requests aren't affected.
+    pub fn set_timeout(&self, timeout: Option<Duration>) {
+        *self.timeout.write().unwrap() = timeout;
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Response> {
+        let timeout = self.timeout();
+        let result = self.send_recorded(request).await;
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(v) => tracing::debug!(status = v.status().as_u16(), "device responded"),
+            Err(e) => tracing::debug!(error = %e, "request failed"),
+        }
+        result.map_err(|e| timeout_error(e, timeout, self.connect_timeout))
+    }
+
+    async fn send_recorded(&self, request: reqwest::RequestBuilder) -> reqwest::Result<Response> {
+        let Some(recorder) = &self.recorder else {
+            return self.send_with_retries(request).await;
+        };
+
+        let sent = request
+            .try_clone()
+            .expect("request body is always buffered")
+            .build()?;
+        let response = self.send_with_retries(request).await?;
+
+        // Body could be read only once, so response is rebuilt from already read one
+        let mut recorded = http::Response::builder()
+            .status(response.status())
+            .version(response.version());
+        if let Some(headers) = recorded.headers_mut() {
+            headers.extend(response.headers().clone());
+        }
+        let body = response.bytes().await?;
+        let _ = recorder.record(
+            sent.url().path(),
+            sent.body().and_then(|v| v.as_bytes()).unwrap_or_default(),
+            &body,
+        );
+        Ok(recorded
+            .body(body)
+            .expect("response parts are taken from valid response")
+            .into())
+    }
+
+    async fn send_with_retries(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> reqwest::Result<Response> {
+        // Tokio mutex is fair, so requests acquire it in order they were issued. Async-std one
+        // doesn't guarantee that, but still prevents interleaving
+        let _guard = match &self.queue {
+            Some(v) => Some(v.lock().await),
+            None => None,
+        };
+
+        let mut attempt = 0;
+        loop {
+            let result = request
+                .try_clone()
+                .expect("request body is always buffered")
+                .send()
+                .await;
+            if attempt == self.retries {
+                return result;
+            }
+
+            let delay = match &result {
+                Ok(v) if is_busy(v.status()) => {
+                    retry_after(v).unwrap_or_else(|| backoff(self.retry_delay, attempt))
+                }
+                Ok(_) => return result,
+                Err(_) => backoff(self.retry_delay, attempt),
+            };
+            attempt += 1;
+            runtime::sleep(delay).await;
+        }
+    }
+
+    // Same as `post`, but also adds sequence if enabled and records command to be returned by
+    // `last_command`
+    fn command(&self, path: &str, body: Cow<'static, [u8]>) -> reqwest::RequestBuilder {
+        let body = match &self.sequence {
+            Some(v) => Cow::Owned(with_sequence(&body, next_sequence(v))),
+            None => body,
+        };
+        *self.last_command.lock().unwrap() = Some(CommandRecord {
+            endpoint: format!("/zeroconf/{}", path),
+            body: String::from_utf8_lossy(&body).into_owned(),
+            timestamp: SystemTime::now(),
+        });
+        self.post(path, body)
+    }
+
+    /// Returns the last command sent by this client (or any of its clones).
+    ///
+    /// Command is recorded before it's sent, so it's returned even if device failed to apply
+    /// it. Info fetching isn't considered a command and isn't recorded.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// client.set_switch_position(SwitchPosition::On).await;
+    ///
+    /// let got = client.last_command().unwrap();
+    ///
+    /// assert_eq!(got.endpoint, "/zeroconf/switches");
+    /// ```
+    pub fn last_command(&self) -> Option<CommandRecord> {
+        self.last_command.lock().unwrap().clone()
+    }
+
+    /// Fetch device info.
+    ///
+    /// In current implementation it always uses `/zeroconf/info` API and returns limited info of
+    /// default outlet, configured via `ClientBuilder::default_outlet`. For more details take a look
+    /// at `Info` struct.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.fetch_info().await;
+    ///
+    /// assert!(got.is_ok());
+    /// assert_eq!(
+    ///     got.unwrap(),
+    ///     Info {
+    ///         switch: SwitchPosition::Off,
+    ///         startup: StartupPosition::Off,
+    ///         status_led: true,
+    ///         device_id: "1000abcdef".to_string(),
+    ///         fw_version: "1.4.3".to_string(),
+    ///         pulse: Some(Pulse {
+    ///             pulse: PulsePosition::Off,
+    ///             switch: SwitchPosition::Off,
+    ///             outlet: 0,
+    ///             width: PulseWidth(0),
+    ///         }),
+    ///         sled_brightness: None,
+    ///         ssid: None
+    ///     }
+    /// )
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlet = self.default_outlet.get())))]
+    pub async fn fetch_info(&self) -> Result<Info> {
+        let info = self.read_info().await?;
+        let info = Info::for_outlet(info, self.default_outlet.get())?;
+
+        self.check_switch_position(&info.switch)?;
+        Ok(info)
+    }
+
+    /// Fetch info of several devices concurrently.
+    ///
+    /// Results are returned in order of given clients, failure of one device doesn't affect the
+    /// rest ones.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = Client::fetch_info_many(&[living_room, kitchen]).await;
+    ///
+    /// assert_eq!(got.len(), 2);
+    /// ```
+    pub async fn fetch_info_many(clients: &[Client]) -> Vec<Result<Info>> {
+        join_all(clients.iter().map(|v| v.fetch_info())).await
+    }
+
+    /// Fetch current switch position of default outlet.
+    ///
+    /// Same as `fetch_info`, but returns only switch position, so startup position and the rest
+    /// details aren't required to be reported.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.get_switch_position().await;
+    ///
+    /// assert_eq!(got.unwrap(), SwitchPosition::Off);
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlet = self.default_outlet.get())))]
+    pub async fn get_switch_position(&self) -> Result<SwitchPosition> {
+        let info = self.read_info().await?;
+        let outlet = self.default_outlet.get();
+        let position = info
+            .switches
+            .into_iter()
+            .find(|s| s.outlet == outlet)
+            .ok_or_else(|| missing_outlet("switches", outlet))?
+            .switch;
+
+        self.check_switch_position(&position)?;
+        Ok(position)
+    }
+
+    /// Fetch startup position of default outlet.
+    ///
+    /// Same as `fetch_info`, but returns only startup position, so switch position and the rest
+    /// details aren't required to be reported.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.get_startup_position().await;
+    ///
+    /// assert_eq!(got.unwrap(), StartupPosition::Off);
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlet = self.default_outlet.get())))]
+    pub async fn get_startup_position(&self) -> Result<StartupPosition> {
+        let info = self.read_info().await?;
+        let outlet = self.default_outlet.get();
+        Ok(info
+            .configure
+            .into_iter()
+            .find(|s| s.outlet == outlet)
+            .ok_or_else(|| missing_outlet("configure", outlet))?
+            .startup)
+    }
+
+    /// Fetch device info, parsing every field independently.
+    ///
+    /// Same as `fetch_info`, but malformed field doesn't fail whole fetch, so the rest fields
+    /// could still be read. For more details take a look at `PartialInfo` struct.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.fetch_info_lenient().await.unwrap();
+    ///
+    /// assert_eq!(got.switch, Ok(SwitchPosition::Off));
+    /// assert!(got.pulse_width.is_err());
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlet = self.default_outlet.get())))]
+    pub async fn fetch_info_lenient(&self) -> Result<PartialInfo> {
+        let response = self
+            .send(self.post("info", Cow::Borrowed(INFO_REQUEST_BODY)))
+            .await?;
+        let mut info = PartialInfo::for_outlet(
+            read_json::<PartialInfoResponse>(response).await?,
+            self.default_outlet.get(),
+        )?;
+        if let Ok(v) = &info.fw_version {
+            self.forget_stale_capabilities(v);
+        }
+
+        info.switch = info
+            .switch
+            .and_then(|v| self.check_switch_position(&v).map(|_| v));
+        Ok(info)
+    }
+
+    /// Fetch full device info.
+    ///
+    /// Same as `fetch_info`, but returns all details reported by `/zeroconf/info` API. For more
+    /// details take a look at `RawInfo` struct.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.fetch_raw_info().await.unwrap();
+    ///
+    /// assert_eq!(got.fw_version, Some("1.4.3".to_string()));
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host)))]
+    pub async fn fetch_raw_info(&self) -> Result<RawInfo> {
+        let info = self.read_info().await?;
+
+        for s in &info.switches {
+            self.check_switch_position(&s.switch)?;
+        }
+        Ok(info)
+    }
+
+    /// Fetch and decode device info, every info fetch except lenient one goes through it.
+    async fn read_info(&self) -> Result<RawInfo> {
+        let response = self
+            .send(self.post("info", Cow::Borrowed(INFO_REQUEST_BODY)))
+            .await?;
+        let info: RawInfo = read_json::<InfoResponse>(response).await?.try_into()?;
+
+        self.forget_stale_capabilities(&info.fw_version);
+        Ok(info)
+    }
+
+    // Cached capabilities are valid only for firmware they were determined for
+    fn forget_stale_capabilities(&self, fw_version: &Option<String>) {
+        let mut capabilities = self.capabilities.lock().unwrap();
+        if matches!(&*capabilities, Some(v) if &v.fw_version != fw_version) {
+            *capabilities = None;
+        }
+    }
+
+    /// Determine capabilities of device, reusing previously determined ones.
+    ///
+    /// Device is probed only on the first call, or if firmware version reported by any later info
+    /// fetch differs from the one capabilities were determined for.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.capabilities_cached().await.unwrap();
+    ///
+    /// assert!(got.multi_outlet);
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host)))]
+    pub async fn capabilities_cached(&self) -> Result<Capabilities> {
+        if let Some(v) = self.capabilities.lock().unwrap().clone() {
+            return Ok(v);
+        }
+        self.refresh_capabilities().await
+    }
+
+    /// Probe device for its capabilities, replacing cached ones.
+    ///
+    /// If firmware reports list of supported commands, it's used to refine capabilities.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host)))]
+    pub async fn refresh_capabilities(&self) -> Result<Capabilities> {
+        let mut capabilities = Capabilities::from(&self.fetch_raw_info().await?);
+        // Most firmware doesn't have this API, so fall back to info-based heuristics
+        if let Ok(v) = self.fetch_supported_commands().await {
+            capabilities = capabilities.with_supported_commands(&v);
+        }
+        *self.capabilities.lock().unwrap() = Some(capabilities.clone());
+        Ok(capabilities)
+    }
+
+    /// Fetch faults reported by device.
+    ///
+    /// Faults are read from `/zeroconf/info` API, so for firmware that doesn't report them all
+    /// fields are `None`. API doesn't provide a way to clear faults.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.fetch_faults().await.unwrap();
+    ///
+    /// assert_eq!(got.count, Some(3));
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host)))]
+    pub async fn fetch_faults(&self) -> Result<Faults> {
+        Ok(self.fetch_raw_info().await?.into())
+    }
+
+    /// Fetch pulse duration of default outlet.
+    ///
+    /// Reported value is converted according to `ClientBuilder::pulse_width_unit`. `None` is
+    /// returned if firmware doesn't report it.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.fetch_pulse_width().await.unwrap();
+    ///
+    /// assert_eq!(got, Some(Duration::from_millis(1500)));
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlet = self.default_outlet.get())))]
+    pub async fn fetch_pulse_width(&self) -> Result<Option<Duration>> {
+        let info = self.fetch_raw_info().await?;
+        let width = info.pulse_width.or_else(|| {
+            info.pulses
+                .iter()
+                .flatten()
+                .find(|v| v.outlet == self.default_outlet.get())
+                .map(|v| v.width)
+        });
+        Ok(width.map(|v| v.as_duration(self.pulse_width_unit)))
+    }
+
+    /// Fetch list of commands supported by device.
+    ///
+    /// It uses `/zeroconf/commands` API, which is supported only by some firmware. Successful
+    /// response without the list is rejected with `Error::UnexpectedResponse`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.fetch_supported_commands().await.unwrap();
+    ///
+    /// assert!(got.contains(&"pulse".to_string()));
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host)))]
+    pub async fn fetch_supported_commands(&self) -> Result<Vec<String>> {
+        let response = self
+            .send(self.post("commands", Cow::Borrowed(INFO_REQUEST_BODY)))
+            .await?;
+        read_json::<SupportedCommandsResponse>(response)
+            .await?
+            .try_into()
+    }
+
+    /// Fetch WiFi signal strength.
+    ///
+    /// It uses `/zeroconf/signal_strength` API. For more details take a look at `Rssi` struct.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.fetch_signal_strength().await.unwrap();
+    ///
+    /// assert_eq!(got, Rssi(-67));
+    /// assert_eq!(got.quality_percent(), 66);
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host)))]
+    pub async fn fetch_signal_strength(&self) -> Result<Rssi> {
+        let response = self
+            .send(self.post("signal_strength", Cow::Borrowed(INFO_REQUEST_BODY)))
+            .await?;
+        read_json::<SignalStrengthResponse>(response)
+            .await?
+            .try_into()
+    }
+
+    /// Check whether device is advertising for pairing.
+    ///
+    /// Firmware that doesn't report it is considered to operate normally.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.is_pairing().await.unwrap();
+    ///
+    /// assert!(!got);
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host)))]
+    pub async fn is_pairing(&self) -> Result<bool> {
+        Ok(self.fetch_raw_info().await?.pairing.unwrap_or(false))
+    }
+
+    /// Fetch current and startup switch positions of every outlet.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.fetch_snapshot().await.unwrap();
+    ///
+    /// assert_eq!(got[0].switch, SwitchPosition::On);
+    /// assert_eq!(got[0].startup, StartupPosition::Stay);
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host)))]
+    pub async fn fetch_snapshot(&self) -> Result<Vec<OutletSnapshot>> {
+        Ok(self.fetch_raw_info().await?.snapshot())
+    }
+
+    /// Wait for device to come back online and verify that it runs expected firmware.
+    ///
+    /// Intended to be used after flashing, when device reboots and is unreachable for a while.
+    /// Device info is polled until device reports expected version or `timeout` expires, in the
+    /// latter case the last error is returned: `Error::FirmwareMismatch` if device still runs
+    /// another firmware version, fetch error if it's unreachable or `Error::TimedOut` if it didn't
+    /// respond before `timeout`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.verify_firmware("3.5.0", Duration::from_secs(60)).await;
+    ///
+    /// assert!(got.is_ok());
+    /// ```
+    ///
+    /// Available only with `ota` feature.
+    #[cfg(feature = "ota")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, expected_version)))]
+    pub async fn verify_firmware(&self, expected_version: &str, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let e = match runtime::timeout(remaining, self.fetch_raw_info()).await {
+                Some(Ok(info)) => match info.fw_version {
+                    Some(v) if v == expected_version => return Ok(()),
+                    actual => Error::FirmwareMismatch {
+                        expected: expected_version.to_string(),
+                        actual,
+                    },
+                },
+                Some(Err(e)) => e,
+                None => Error::TimedOut(timeout),
+            };
+            if Instant::now() + VERIFY_POLL_INTERVAL >= deadline {
+                return Err(e);
+            }
+            runtime::sleep(VERIFY_POLL_INTERVAL).await;
+        }
+    }
+
+    fn check_switch_position(&self, position: &SwitchPosition) -> Result<()> {
+        check_switch_position(position, self.lenient)
+    }
+
+    /// Set startup position for device.
+    ///
+    /// It uses `/zeroconf/startups` API and sets given position only for default outlet,
+    /// configured via `ClientBuilder::default_outlet`. Other outlets are left untouched, use
+    /// `set_startup_positions` to configure several outlets at once.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.set_startup_position(StartupPosition::Stay).await;
+    ///
+    /// assert!(got.is_ok());
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlet = self.default_outlet.get(), ?position)))]
+    pub async fn set_startup_position(&self, position: StartupPosition) -> Result<()> {
+        let response = self
+            .send(self.command(
+                "startups",
+                Cow::Owned(serde_json::to_vec(&StartupsRequest::for_positions(&[(
+                    self.default_outlet,
+                    position,
+                )]))?),
+            ))
+            .await?;
+        parse_empty_response(response).await
+    }
+
+    /// Set startup positions for given outlets only, other outlets are left untouched.
+    ///
+    /// Each outlet should be given once, otherwise `Error::InvalidParameter` is returned without
+    /// sending anything.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client
+    ///     .set_startup_positions(&[
+    ///         (OutletIndex::new(1)?, StartupPosition::On),
+    ///         (OutletIndex::new(3)?, StartupPosition::Stay),
+    ///     ])
+    ///     .await;
+    ///
+    /// assert!(got.is_ok());
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, ?configs)))]
+    pub async fn set_startup_positions(
+        &self,
+        configs: &[(OutletIndex, StartupPosition)],
+    ) -> Result<()> {
+        if configs.is_empty() {
+            return Err(Error::InvalidParameter(
+                "configs must not be empty".to_string(),
+            ));
+        }
+        check_unique_outlets(configs)?;
+
+        let response = self
+            .send(self.command(
+                "startups",
+                Cow::Owned(serde_json::to_vec(&StartupsRequest::for_positions(
+                    configs,
+                ))?),
+            ))
+            .await?;
+        parse_empty_response(response).await
+    }
+
+    /// Set pulse (inching) for given outlet.
+    ///
+    /// It uses `/zeroconf/pulse` API. If `mode` is `None`, device keeps mode it's configured
+    /// with. Width should be a multiple of 500ms within 500ms..=10h, otherwise
+    /// `Error::InvalidParameter` is returned without sending anything.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client
+    ///     .set_pulse(
+    ///         OutletIndex::new(0)?,
+    ///         PulsePosition::On,
+    ///         Duration::from_millis(1500),
+    ///         Some(PulseMode::DelayOn),
+    ///     )
+    ///     .await;
+    ///
+    /// assert!(got.is_ok());
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlet = outlet.get(), ?pulse, ?width, ?mode)))]
+    pub async fn set_pulse(
+        &self,
+        outlet: OutletIndex,
+        pulse: PulsePosition,
+        width: Duration,
+        mode: Option<PulseMode>,
+    ) -> Result<()> {
+        let request = PulseRequest::new(outlet, pulse, width, mode)?;
+        let response = self
+            .send(self.command("pulse", Cow::Owned(serde_json::to_vec(&request)?)))
+            .await?;
+        parse_empty_response(response).await
+    }
+
+    /// Cancel pulse on default outlet, aborting active countdown.
+    ///
+    /// It uses `/zeroconf/pulse` API. If device reports that there is no active pulse to cancel
+    /// (code 409), `Ok` is returned.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.cancel_pulse().await;
+    ///
+    /// assert!(got.is_ok());
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlet = self.default_outlet.get())))]
+    pub async fn cancel_pulse(&self) -> Result<()> {
+        let response = self
+            .send(self.command(
+                "pulse",
+                Cow::Owned(serde_json::to_vec(&PulseRequest::cancel(
+                    self.default_outlet.get(),
+                ))?),
+            ))
+            .await?;
+        read_json::<EmptyResponse>(response)
+            .await?
+            .into_cancel_pulse_result()
+    }
+
+    /// Set switch position.
+    ///
+    /// Is uses `/zeroconf/switches` API and sets given position for default outlets only (outlet 0
+    /// unless configured via `ClientBuilder::default_outlets`). This API allows to ignore state of
+    /// another outlets, so they will be ignored.
+    ///
+    /// Older single-channel firmware doesn't have `/zeroconf/switches` API, so if it's not found
+    /// request is retried against `/zeroconf/switch`.
+    ///
+    /// Besides `SwitchPosition`, `bool` could be given, where `true` means `On`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let got = client.set_switch_position(SwitchPosition::On).await;
+    ///
+    /// assert!(got.is_err());
+    /// assert_eq!(
+    ///     got.unwrap_err(),
+    ///     Error::WrongParameters(None)
+    /// )
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlets = ?self.default_outlets)))]
+    pub async fn set_switch_position(&self, position: impl Into<SwitchPosition>) -> Result<()> {
+        let position = position.into();
+        let positions: Vec<_> = self
+            .default_outlets
+            .iter()
+            .map(|outlet| (*outlet, position.clone()))
+            .collect();
+
+        self.set_switch_positions(&positions).await
+    }
+
+    /// Set switch positions for given outlets, other outlets are left untouched.
+    ///
+    /// Singular `/zeroconf/switch` fallback is used only when outlet 0 is the only one given,
+    /// since older firmware has no other outlets.
+    ///
+    /// Each outlet should be given once, otherwise `Error::InvalidParameter` is returned without
+    /// sending anything.
+    ///
+    /// # Example
+    /// ```ignore
+    /// client
+    ///     .set_switch_positions(&[
+    ///         (OutletIndex::new(0)?, SwitchPosition::On),
+    ///         (OutletIndex::new(2)?, SwitchPosition::Off),
+    ///     ])
+    ///     .await?;
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, ?positions)))]
+    pub async fn set_switch_positions(
+        &self,
+        positions: &[(OutletIndex, SwitchPosition)],
+    ) -> Result<()> {
+        if positions.is_empty() {
+            return Err(Error::InvalidParameter(
+                "positions must not be empty".to_string(),
+            ));
+        }
+        check_unique_outlets(positions)?;
+
+        let body = match positions {
+            [(FIRST_OUTLET, position)] => SwitchesRequest::body(position.clone()),
+            _ => Cow::Owned(serde_json::to_vec(&SwitchesRequest::for_positions(
+                positions,
+            ))?),
+        };
+        let mut response = self.send(self.command("switches", body)).await?;
+
+        if let [(FIRST_OUTLET, position)] = positions {
+            if response.status() == StatusCode::NOT_FOUND {
+                response = self
+                    .send(self.command(
+                        "switch",
+                        Cow::Owned(serde_json::to_vec(&SwitchRequest::from(position.clone()))?),
+                    ))
+                    .await?;
+            }
+        }
+
+        parse_empty_response(response).await
+    }
+
+    /// Set switch position only if current one matches `expected`.
+    ///
+    /// Returns whether position was set. Current position is read right before setting new one,
+    /// all default outlets should be in `expected` position and default outlet not reported by
+    /// device is rejected with `Error::MalformedField`. Note that device doesn't support atomic
+    /// updates, so it only narrows window for races with other controllers.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client
+    ///     .compare_and_set_switch(SwitchPosition::Off, SwitchPosition::On)
+    ///     .await;
+    ///
+    /// assert!(got.unwrap());
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlets = ?self.default_outlets, ?expected, ?new)))]
+    pub async fn compare_and_set_switch(
+        &self,
+        expected: SwitchPosition,
+        new: SwitchPosition,
+    ) -> Result<bool> {
+        let info = self.fetch_raw_info().await?;
+        for outlet in self.default_outlets.iter().map(|v| v.get()) {
+            let current = info
+                .switches
+                .iter()
+                .find(|v| v.outlet == outlet)
+                .ok_or_else(|| missing_outlet("switches", outlet))?;
+            if current.switch != expected {
+                return Ok(false);
+            }
+        }
+
+        self.set_switch_position(new).await?;
+        Ok(true)
+    }
+
+    /// Invert current switch position and return the new one.
+    ///
+    /// Current position is read from default outlet with `fetch_info`, then inverted one is set with
+    /// `set_switch_position`. These are two separate requests, so switch changed in between by
+    /// another controller or concurrent call would be overwritten. Unknown position reported to
+    /// lenient client couldn't be inverted and is rejected with `Error::UnexpectedSwitchPosition`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.toggle_switch().await;
+    ///
+    /// assert_eq!(got.unwrap(), SwitchPosition::On);
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlets = ?self.default_outlets)))]
+    pub async fn toggle_switch(&self) -> Result<SwitchPosition> {
+        let new = match self.fetch_info().await?.switch {
+            SwitchPosition::On => SwitchPosition::Off,
+            SwitchPosition::Off => SwitchPosition::On,
+            SwitchPosition::Unknown(v) => return Err(Error::UnexpectedSwitchPosition(v)),
+        };
+
+        self.set_switch_position(new.clone()).await?;
+        Ok(new)
+    }
+
+    /// Switch default outlets on, same as `set_switch_position(SwitchPosition::On)`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlets = ?self.default_outlets)))]
+    pub async fn turn_on(&self) -> Result<()> {
+        self.set_switch_position(SwitchPosition::On).await
+    }
+
+    /// Switch default outlets off, same as `set_switch_position(SwitchPosition::Off)`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, outlets = ?self.default_outlets)))]
+    pub async fn turn_off(&self) -> Result<()> {
+        self.set_switch_position(SwitchPosition::Off).await
+    }
+}
+
+impl Client {
+    /// Set brightness of network status LED in percents.
+    ///
+    /// It uses `/zeroconf/sledBrightness` API, which is supported only by some firmware. Level
+    /// should be within `0..=100`, otherwise `Error::InvalidParameter` is returned without
+    /// sending request.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.set_sled_brightness(30).await;
+    ///
+    /// assert!(got.is_ok());
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, level)))]
+    pub async fn set_sled_brightness(&self, level: u8) -> Result<()> {
+        let body = serde_json::to_vec(&SledBrightnessRequest::try_from(level)?)?;
+        let response = self
+            .send(self.command("sledBrightness", Cow::Owned(body)))
+            .await?;
+        parse_empty_response(response).await
+    }
+
+    /// Enable or disable network status LED.
+    ///
+    /// It uses `/zeroconf/sledonline` API.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.set_status_led(false).await;
+    ///
+    /// assert!(got.is_ok());
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, on)))]
+    pub async fn set_status_led(&self, on: bool) -> Result<()> {
+        let body = serde_json::to_vec(&StatusLedRequest::from(on))?;
+        let response = self
+            .send(self.command("sledonline", Cow::Owned(body)))
+            .await?;
+        parse_empty_response(response).await
+    }
+
+    /// Set WiFi credentials.
+    ///
+    /// It uses `/zeroconf/wifi` API, device responds before it disconnects to join given network,
+    /// so result tells whether it accepted credentials or not. Device may drop connection right
+    /// after responding with 200, it's considered as accepted credentials. Empty SSID is rejected
+    /// with `Error::InvalidParameter` without sending anything.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.set_wifi("home", "secret").await;
+    ///
+    /// assert_eq!(got.unwrap(), WifiSetResult::Accepted);
+    /// ```
+    ///
+    /// Available only with `wifi` feature.
+    #[cfg(feature = "wifi")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, ssid)))]
+    pub async fn set_wifi(&self, ssid: &str, password: &str) -> Result<WifiSetResult> {
+        if ssid.is_empty() {
+            return Err(Error::InvalidParameter(
+                "ssid must not be empty".to_string(),
+            ));
+        }
+
+        let body = serde_json::to_vec(&WifiRequest::new(ssid, password))?;
+        let response = self.send(self.command("wifi", Cow::Owned(body))).await?;
+        let status = response.status();
+        check_status(status)?;
+        let body = match response.bytes().await {
+            Ok(v) => v,
+            // Device is already leaving its AP to join given network
+            Err(_) if status == StatusCode::OK => return Ok(WifiSetResult::Accepted),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(parse_json::<EmptyResponse>(&body)?.into())
+    }
+
+    /// Unlock OTA updates, which is required before flashing firmware.
+    ///
+    /// It uses `/zeroconf/ota_unlock` API. Device verifies given id and rejects unlock with
+    /// `Error::InvalidDeviceId` if it doesn't match, other failures are reported as
+    /// `Error::OtaUnlockFailed`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client.ota_unlock("1000abcdef").await;
+    ///
+    /// assert!(got.is_ok());
+    /// ```
+    ///
+    /// Available only with `ota` feature.
+    #[cfg(feature = "ota")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, device_id)))]
+    pub async fn ota_unlock(&self, device_id: &str) -> Result<()> {
+        let body = serde_json::to_vec(&OtaUnlockRequest::new(device_id))?;
+        let response = self
+            .send(self.command("ota_unlock", Cow::Owned(body)))
+            .await?;
+        read_json::<EmptyResponse>(response)
+            .await?
+            .into_ota_unlock_result(device_id)
+    }
+
+    /// Flash firmware downloaded by device from given URL.
+    ///
+    /// It uses `/zeroconf/ota_flash` API, OTA should be unlocked with `ota_unlock` first.
+    /// `sha256sum` should be 64 hex digits, otherwise `Error::InvalidSha256` is returned without
+    /// sending anything. Device reports failures with distinct codes, they are returned as
+    /// `Error::OtaLocked` (403), `Error::FirmwareDownloadTimedOut` (408),
+    /// `Error::FirmwareTooLarge` (413), `Error::FirmwareDownloadFailed` (424) and
+    /// `Error::FirmwareChecksumMismatch` (471).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// client.ota_unlock("1000abcdef").await?;
+    ///
+    /// let got = client
+    ///     .ota_flash("http://192.168.1.10/firmware.bin", sha256sum)
+    ///     .await;
+    ///
+    /// assert!(got.is_ok());
+    /// ```
+    ///
+    /// Available only with `ota` feature.
+    #[cfg(feature = "ota")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(host = %self.host, download_url)))]
+    pub async fn ota_flash(&self, download_url: &str, sha256sum: &str) -> Result<()> {
+        let body = serde_json::to_vec(&OtaFlashRequest::new(download_url, sha256sum)?)?;
+        let response = self
+            .send(self.command("ota_flash", Cow::Owned(body)))
+            .await?;
+        read_json::<EmptyResponse>(response)
+            .await?
+            .into_ota_flash_result()
+    }
+}
+
+/// Builders of requests exactly as they are sent by client, for offline verification.
+///
+/// Each builder returns endpoint path and request body. No network is involved, so they could be
+/// used e.g. to snapshot-test payloads.
+///
+/// # Example
+///
+/// ```
+/// # use sonoff_minir3::{Client, OutletIndex, SwitchPosition};
+///
+/// let (endpoint, body) =
+///     Client::build_switch_request(SwitchPosition::On, OutletIndex::new(0).unwrap());
+///
+/// assert_eq!(endpoint, "/zeroconf/switches");
+/// assert_eq!(body, br#"{"data":{"switches":[{"switch":"on","outlet":0}]}}"#);
+/// ```
+impl Client {
+    /// Request sent by `fetch_info` and `fetch_raw_info`
+    pub fn build_info_request() -> (String, Vec<u8>) {
+        ("/zeroconf/info".to_string(), INFO_REQUEST_BODY.to_vec())
+    }
+
+    /// Request sent by `set_startup_position`, but for given outlet
+    pub fn build_startup_request(
+        position: StartupPosition,
+        outlet: OutletIndex,
+    ) -> (String, Vec<u8>) {
+        (
+            "/zeroconf/startups".to_string(),
+            serde_json::to_vec(&StartupsRequest::for_positions(&[(outlet, position)])).unwrap(),
+        )
+    }
+
+    /// Request sent by `set_switch_position`, but for given outlet
+    pub fn build_switch_request(
+        position: SwitchPosition,
+        outlet: OutletIndex,
+    ) -> (String, Vec<u8>) {
+        (
+            "/zeroconf/switches".to_string(),
+            serde_json::to_vec(&SwitchesRequest::for_positions(&[(outlet, position)])).unwrap(),
+        )
+    }
+
+    /// Request sent by `set_sled_brightness`
+    pub fn build_sled_brightness_request(level: u8) -> Result<(String, Vec<u8>)> {
+        Ok((
+            "/zeroconf/sledBrightness".to_string(),
+            serde_json::to_vec(&SledBrightnessRequest::try_from(level)?).unwrap(),
+        ))
+    }
+
+    /// Request sent by `set_status_led`
+    pub fn build_status_led_request(on: bool) -> (String, Vec<u8>) {
+        (
+            "/zeroconf/sledonline".to_string(),
+            serde_json::to_vec(&StatusLedRequest::from(on)).unwrap(),
+        )
+    }
+
+    /// Request sent by `set_pulse`
+    pub fn build_pulse_request(
+        outlet: OutletIndex,
+        pulse: PulsePosition,
+        width: Duration,
+        mode: Option<PulseMode>,
+    ) -> Result<(String, Vec<u8>)> {
+        Ok((
+            "/zeroconf/pulse".to_string(),
+            serde_json::to_vec(&PulseRequest::new(outlet, pulse, width, mode)?).unwrap(),
+        ))
+    }
+
+    /// Request sent by `cancel_pulse`, but for given outlet
+    pub fn build_cancel_pulse_request(outlet: OutletIndex) -> (String, Vec<u8>) {
+        (
+            "/zeroconf/pulse".to_string(),
+            serde_json::to_vec(&PulseRequest::cancel(outlet.get())).unwrap(),
+        )
+    }
+
+    /// Request sent by `ota_unlock`
+    #[cfg(feature = "ota")]
+    pub fn build_ota_unlock_request(device_id: &str) -> (String, Vec<u8>) {
+        (
+            "/zeroconf/ota_unlock".to_string(),
+            serde_json::to_vec(&OtaUnlockRequest::new(device_id)).unwrap(),
+        )
+    }
+
+    /// Request sent by `ota_flash`
+    #[cfg(feature = "ota")]
+    pub fn build_ota_flash_request(
+        download_url: &str,
+        sha256sum: &str,
+    ) -> Result<(String, Vec<u8>)> {
+        Ok((
+            "/zeroconf/ota_flash".to_string(),
+            serde_json::to_vec(&OtaFlashRequest::new(download_url, sha256sum)?).unwrap(),
+        ))
+    }
+
+    /// Request sent by `set_wifi`
+    #[cfg(feature = "wifi")]
+    pub fn build_wifi_request(ssid: &str, password: &str) -> (String, Vec<u8>) {
+        (
+            "/zeroconf/wifi".to_string(),
+            serde_json::to_vec(&WifiRequest::new(ssid, password)).unwrap(),
+        )
+    }
+}
+
+/// Run operation consisting of several requests within total time budget.
+///
+/// If budget is exceeded, remaining requests are cancelled and `Error::BudgetExceeded` is
+/// returned.
+///
+/// # Example
+///
+/// ```ignore
+/// let got = with_budget(Duration::from_secs(2), async {
+///     client.set_startup_position(StartupPosition::Stay).await?;
+///     client.set_switch_position(SwitchPosition::On).await
+/// })
+/// .await;
+/// ```
+pub async fn with_budget<T, F>(budget: Duration, operation: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    match runtime::timeout(budget, operation).await {
+        Some(v) => v,
+        None => Err(Error::BudgetExceeded(budget)),
+    }
+}
+
+// All commands that don't return anything should use this to parse their responses, so their
+// success and error handling is identical
+async fn parse_empty_response(response: Response) -> Result<()> {
+    read_json::<EmptyResponse>(response).await?.try_into()
+}
+
+// Body is read completely before parsing, so responses without content length (e.g. chunked
+// ones) are handled the same way as others
+async fn read_json<T: DeserializeOwned>(response: Response) -> Result<T> {
+    check_status(response.status())?;
+    let body = response.bytes().await?;
+    parse_json(&body)
+}
+
+fn into_body(body: Cow<'static, [u8]>) -> reqwest::Body {
+    match body {
+        Cow::Borrowed(v) => v.into(),
+        Cow::Owned(v) => v.into(),
+    }
+}
+
+// HTTP client configured the way builder does, policy is wrapped on every call since pool rebuilds
+// client on reaping, while policy itself couldn't be cloned
+pub(crate) fn http_client(
+    connect_timeout: Option<Duration>,
+    redirect_policy: Option<Arc<Policy>>,
+) -> reqwest::Result<reqwest::Client> {
+    let policy = match redirect_policy {
+        Some(v) => Policy::custom(move |attempt| v.redirect(attempt)),
+        None => Policy::custom(preserving_redirect),
+    };
+    let mut builder = reqwest::Client::builder()
+        .http1_title_case_headers()
+        .redirect(policy);
+    if let Some(v) = connect_timeout {
+        builder = builder.connect_timeout(v);
+    }
+    builder.build()
+}
+
+fn preserving_redirect(attempt: Attempt) -> reqwest::redirect::Action {
+    if attempt.previous().len() > MAX_REDIRECTS {
+        return attempt.error("too many redirects");
+    }
+    match attempt.status() {
+        StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT => attempt.follow(),
+        _ => attempt.stop(),
+    }
+}
+
+fn next_sequence(last: &AtomicU64) -> u64 {
+    let now = SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    let previous = last
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| {
+            Some(now.max(v + 1))
+        })
+        .unwrap();
+    now.max(previous + 1)
+}
+
+// Every command body is a JSON object, so sequence is added at its top level
+fn with_sequence(body: &[u8], sequence: u64) -> Vec<u8> {
+    let mut value: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(body).expect("command body is a JSON object");
+    value.insert("sequence".to_string(), sequence.to_string().into());
+    serde_json::to_vec(&value).unwrap()
+}
+
+// Delay before retry following given number of attempts, doubled with every attempt
+fn backoff(base: Duration, attempt: usize) -> Duration {
+    let factor = 2u32.saturating_pow(attempt.try_into().unwrap_or(u32::MAX));
+    base.saturating_mul(factor)
+}
+
+fn is_busy(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE
+}
+
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+async fn resolve_addr<F, R>(host: &str, port: u16, lookup: F) -> Result<SocketAddr>
+where
+    F: FnOnce(String) -> R,
+    R: Future<Output = io::Result<Vec<SocketAddr>>>,
+{
+    // IPv6 literals are bracketed to be used in URL
+    let literal = host.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = literal.parse::<IpAddr>() {
+        return Ok(SocketAddr::new(ip, port));
+    }
+
+    lookup(format!("{}:{}", host, port))
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} resolved to no addresses", host),
+            )
+            .into()
+        })
+}
+
+// Device applies entries in unspecified order, so conflicting entries of the same outlet are rejected
+fn check_unique_outlets<P>(positions: &[(OutletIndex, P)]) -> Result<()> {
+    for (i, (outlet, _)) in positions.iter().enumerate() {
+        if positions[..i].iter().any(|(v, _)| v == outlet) {
+            return Err(Error::InvalidParameter(format!(
+                "duplicate outlet {}",
+                outlet.get()
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Unknown positions are accepted only by lenient clients
+pub(crate) fn check_switch_position(position: &SwitchPosition, lenient: bool) -> Result<()> {
+    match position {
+        SwitchPosition::Unknown(v) if !lenient => Err(Error::UnexpectedSwitchPosition(v.clone())),
+        _ => Ok(()),
+    }
+}
+
+// Timeouts are reported as `Error::TimedOut` with configured timeout that fired, connect one is
+// reported only if it fired while connecting
+pub(crate) fn timeout_error(
+    e: reqwest::Error,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+) -> Error {
+    if !e.is_timeout() {
+        return e.into();
+    }
+    let fired = match connect_timeout {
+        Some(v) if e.is_connect() => Some(v),
+        _ => timeout,
+    };
+    match fired {
+        Some(v) => Error::TimedOut(v),
+        None => e.into(),
+    }
+}
+
+pub(crate) fn zeroconf_url(scheme: Scheme, host: &str, port: u16, path: &str) -> String {
+    format!(
+        "{scheme}://{host}:{port}/zeroconf/{path}",
+        scheme = scheme.as_str(),
+        host = url_host(host),
+    )
+}
+
+// IPv6 literals are bracketed in URL, so their colons aren't mistaken for port separator
+fn url_host(host: &str) -> Cow<'_, str> {
+    match host.parse::<Ipv6Addr>() {
+        Ok(_) => Cow::Owned(format!("[{}]", host)),
+        Err(_) => Cow::Borrowed(host),
+    }
+}
+
+// URL can't hold zone of scoped IPv6 address, so it would be silently dropped
+fn check_unscoped(host: &str) -> Result<()> {
+    match host.contains('%') {
+        true => Err(Error::InvalidParameter(format!(
+            "scoped IPv6 address {} is unsupported",
+            host
+        ))),
+        false => Ok(()),
+    }
+}
+
+// IP literals are stored without brackets, so they're formatted the same way as `SocketAddr` ones
+fn split_addr(addr: &str) -> Result<(String, u16)> {
+    let invalid = || Error::InvalidParameter(format!("invalid address {}", addr));
+
+    check_unscoped(addr)?;
+
+    if let Ok(v) = addr.parse::<SocketAddr>() {
+        return Ok((v.ip().to_string(), v.port()));
+    }
+    if let Ok(v) = addr
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        return Ok((v.to_string(), DEFAULT_PORT));
+    }
+
+    let (host, port) = match addr.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|_| invalid())?),
+        None => (addr, DEFAULT_PORT),
+    };
+    if host.is_empty() || host.contains(|c: char| c.is_whitespace() || "[]:/".contains(c)) {
+        return Err(invalid());
+    }
+    Ok((host.to_string(), port))
+}
+
+fn gzip(body: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder
+        .write_all(body)
+        .and_then(|_| encoder.finish())
+        .expect("writing to Vec never fails")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use httpmock::MockServer;
+
+    fn make_server_and_lenient_client() -> (MockServer, Client) {
+        let server = MockServer::start();
+        let client = Client::builder(server.host(), server.port())
+            .lenient(true)
+            .build()
+            .unwrap();
+        (server, client)
+    }
+
+    mod info {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/info")
+                    .body("{\"data\":{}}");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_ok.json"));
+            });
+
+            let got = client.fetch_info().await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+            assert_eq!(got.unwrap(), info())
+        }
+
+        #[tokio::test]
+        async fn errored_in_expected_way() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/info")
+                    .body("{\"data\":{}}");
+                then.status(400)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_error.json"));
+            });
+
+            let got = client.fetch_info().await;
+
+            mock.assert();
+
+            assert!(got.is_err());
+            assert_eq!(got.unwrap_err(), Error::WrongParameters(None))
+        }
+
+        #[tokio::test]
+        async fn returns_empty_device_details_if_not_reported() {
+            let (_server, client) =
+                mock_client_returning("info", "response_info_no_device_details.json");
+
+            let got = client.fetch_info().await.unwrap();
+
+            assert_eq!(got.device_id, "");
+            assert_eq!(got.fw_version, "");
+        }
+
+        #[tokio::test]
+        async fn errored_with_reported_message() {
+            let (_server, client) = mock_client_returning("info", "response_error_message.json");
+
+            let got = client.fetch_info().await;
+
+            assert_eq!(
+                got.unwrap_err(),
+                Error::WrongParameters(Some("data.switches outlet invalid".to_string()))
+            )
+        }
+
+        #[tokio::test]
+        async fn errored_on_unknown_error_code() {
+            let (_server, client) = mock_client_returning("info", "response_error_500.json");
+
+            let got = client.fetch_info().await;
+
+            assert_eq!(got.unwrap_err(), Error::Unknown(500, None))
+        }
+
+        #[tokio::test]
+        async fn errored_if_data_is_missing() {
+            let (_server, client) = mock_client_returning("info", "response_info_no_data.json");
+
+            let got = client.fetch_info().await;
+
+            assert_eq!(
+                got.unwrap_err(),
+                Error::UnexpectedResponse("data is missing".to_string())
+            )
+        }
+
+        #[tokio::test]
+        async fn errored_on_server_error_status() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(502)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_ok.json"));
+            });
+
+            let got = client.fetch_info().await;
+
+            assert_eq!(got.unwrap_err(), Error::HttpStatus(502))
+        }
+
+        #[tokio::test]
+        async fn errored_on_malformed_response() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200).body("not a json");
+            });
+
+            let got = client.fetch_info().await;
+
+            assert!(matches!(
+                got.unwrap_err(),
+                Error::MalformedResponse { body, .. } if body == "not a json"
+            ));
+        }
+
+        #[tokio::test]
+        async fn errored_on_unknown_switch_position() {
+            let (_server, client) =
+                mock_client_returning("info", "response_info_unknown_switch.json");
+
+            let got = client.fetch_info().await;
+
+            assert!(got.is_err());
+            assert_eq!(
+                got.unwrap_err(),
+                Error::UnexpectedSwitchPosition("unavailable".to_string())
+            )
+        }
+
+        #[tokio::test]
+        async fn returns_unknown_switch_position_if_lenient() {
+            let (server, client) = make_server_and_lenient_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_unknown_switch.json"));
+            });
+
+            let got = client.fetch_info().await;
+
+            mock.assert();
+
+            assert_eq!(
+                got.unwrap(),
+                Info {
+                    switch: SwitchPosition::Unknown("unavailable".to_string()),
+                    pulse: None,
+                    ..info()
+                }
+            )
+        }
+    }
+
+    mod fetch_info_many {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_expected_result_in_order() {
+            let (_ok_server, ok) = mock_client_returning("info", "response_info_ok.json");
+            let (_error_server, error) = mock_client_returning("info", "response_error.json");
+
+            let got = Client::fetch_info_many(&[error, ok.clone(), ok]).await;
+
+            assert_eq!(got.len(), 3);
+            assert_eq!(got[0], Err(Error::WrongParameters(None)));
+            assert_eq!(got[1].as_ref().unwrap().switch, SwitchPosition::Off);
+            assert_eq!(got[2].as_ref().unwrap().switch, SwitchPosition::Off);
+        }
+
+        #[tokio::test]
+        async fn returns_empty_result_for_no_clients() {
+            let got = Client::fetch_info_many(&[]).await;
+
+            assert!(got.is_empty());
+        }
+    }
+
+    mod switch_position {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/info")
+                    .body("{\"data\":{}}");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_ok.json"));
+            });
+
+            let got = client.get_switch_position().await;
+
+            mock.assert();
+
+            assert_eq!(got.unwrap(), SwitchPosition::Off)
+        }
+
+        #[tokio::test]
+        async fn returns_position_of_default_outlet() {
+            let server = MockServer::start();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_mixed_outlets.json"));
+            });
+            let client = Client::builder(server.host(), server.port())
+                .default_outlet(1)
+                .build()
+                .unwrap();
+
+            let got = client.get_switch_position().await;
+
+            assert_eq!(got.unwrap(), SwitchPosition::Off)
+        }
+
+        #[tokio::test]
+        async fn errored_in_expected_way() {
+            let (_server, client) = mock_client_returning("info", "response_error.json");
+
+            let got = client.get_switch_position().await;
+
+            assert_eq!(got.unwrap_err(), Error::WrongParameters(None))
+        }
+
+        #[tokio::test]
+        async fn errored_on_unknown_switch_position() {
+            let (_server, client) =
+                mock_client_returning("info", "response_info_unknown_switch.json");
+
+            let got = client.get_switch_position().await;
+
+            assert_eq!(
+                got.unwrap_err(),
+                Error::UnexpectedSwitchPosition("unavailable".to_string())
+            )
+        }
+    }
+
+    mod startup_position {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/info")
+                    .body("{\"data\":{}}");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_ok.json"));
+            });
+
+            let got = client.get_startup_position().await;
+
+            mock.assert();
+
+            assert_eq!(got.unwrap(), StartupPosition::Off)
+        }
+
+        #[tokio::test]
+        async fn returns_position_of_default_outlet() {
+            let server = MockServer::start();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_mixed_outlets.json"));
+            });
+            let client = Client::builder(server.host(), server.port())
+                .default_outlet(3)
+                .build()
+                .unwrap();
+
+            let got = client.get_startup_position().await;
+
+            assert_eq!(got.unwrap(), StartupPosition::Stay)
+        }
+
+        #[tokio::test]
+        async fn returns_position_regardless_of_unknown_switch() {
+            let (_server, client) =
+                mock_client_returning("info", "response_info_unknown_switch.json");
+
+            let got = client.get_startup_position().await;
+
+            assert_eq!(got.unwrap(), StartupPosition::Off)
+        }
+
+        #[tokio::test]
+        async fn errored_in_expected_way() {
+            let (_server, client) = mock_client_returning("info", "response_error.json");
+
+            let got = client.get_startup_position().await;
+
+            assert_eq!(got.unwrap_err(), Error::WrongParameters(None))
+        }
+    }
+
+    mod chunked_response {
+        use super::*;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::net::TcpListener;
+
+        #[tokio::test]
+        async fn parsed_completely() {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let server = tokio::spawn(async move {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0; 4096];
+                let _ = stream.read(&mut buf).await.unwrap();
+
+                let body = load_fixture("response_info_ok.json");
+                let mut response = String::from(
+                    "HTTP/1.1 200 OK\r\n\
+                     Content-Type: application/json; charset=utf-8\r\n\
+                     Transfer-Encoding: chunked\r\n\r\n",
+                );
+                for chunk in body.as_bytes().chunks(64) {
+                    response.push_str(&format!(
+                        "{:x}\r\n{}\r\n",
+                        chunk.len(),
+                        std::str::from_utf8(chunk).unwrap()
+                    ));
+                }
+                response.push_str("0\r\n\r\n");
+                stream.write_all(response.as_bytes()).await.unwrap();
+            });
+            let client = Client::new("127.0.0.1", port);
+
+            let got = client.fetch_info().await;
+            server.await.unwrap();
+
+            assert_eq!(got.unwrap(), info())
+        }
+    }
+
+    mod info_lenient {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let (_server, client) = mock_client_returning("info", "response_info_ok.json");
+
+            let got = client.fetch_info_lenient().await;
+
+            assert_eq!(
+                got.unwrap(),
+                PartialInfo {
+                    switch: Ok(SwitchPosition::Off),
+                    startup: Ok(StartupPosition::Off),
+                    pulse_width: Ok(None),
+                    fw_version: Ok(Some("1.4.3".to_string())),
+                }
+            )
+        }
+
+        #[tokio::test]
+        async fn parsed_rest_if_field_is_malformed() {
+            let (_server, client) =
+                mock_client_returning("info", "response_info_malformed_pulse_width.json");
+
+            let got = client.fetch_info_lenient().await.unwrap();
+
+            assert_eq!(got.switch, Ok(SwitchPosition::Off));
+            assert_eq!(got.startup, Ok(StartupPosition::Off));
+            assert_eq!(got.fw_version, Ok(Some("1.4.3".to_string())));
+            assert!(matches!(
+                got.pulse_width,
+                Err(Error::MalformedField { name, .. }) if name == "pulseWidth"
+            ));
+        }
+
+        #[tokio::test]
+        async fn errored_on_unexpected_switch_position() {
+            let (_server, client) =
+                mock_client_returning("info", "response_info_unknown_switch.json");
+
+            let got = client.fetch_info_lenient().await.unwrap();
+
+            assert_eq!(
+                got.switch,
+                Err(Error::UnexpectedSwitchPosition("unavailable".to_string()))
+            );
+            assert_eq!(got.startup, Ok(StartupPosition::Off));
+        }
+
+        #[tokio::test]
+        async fn errored_in_expected_way() {
+            let (_server, client) = mock_client_returning("info", "response_error.json");
+
+            let got = client.fetch_info_lenient().await;
+
+            assert_eq!(got.unwrap_err(), Error::WrongParameters(None))
+        }
+    }
+
+    mod raw_info {
+        use super::*;
+
+        fn expected() -> RawInfo {
+            RawInfo {
+                switches: (0..=3)
+                    .map(|outlet| Switch {
+                        switch: SwitchPosition::Off,
+                        outlet,
+                    })
+                    .collect(),
+                configure: (0..=3)
+                    .map(|outlet| Startup {
+                        startup: StartupPosition::Off,
+                        outlet,
+                    })
+                    .collect(),
+                pulses: Some(
+                    (0..=3)
+                        .map(|outlet| Pulse {
+                            pulse: PulsePosition::Off,
+                            switch: SwitchPosition::Off,
+                            outlet,
+                            width: PulseWidth(0),
+                        })
+                        .collect(),
+                ),
+                pulse_width: None,
+                fw_version: Some("1.4.3".to_string()),
+                device_id: Some("1000abcdef".to_string()),
+                sta_mac: Some("B4:E8:42:FF:60:B6".to_string()),
+                rssi: Some(-34),
+                bssid: Some("50:ff:20:9d:eb:9a".to_string()),
+                ssid: None,
+                free_heap: None,
+                fault_count: None,
+                last_error: None,
+                ip_mode: None,
+                sled_brightness: None,
+                power_watts: None,
+                outlet_names: None,
+                button_press_count: None,
+                last_press_time: None,
+                pairing: None,
+                wifi_security: None,
+                reset_reason: None,
+                ntp_server: None,
+                sled_online: Some(true),
+            }
+        }
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/info")
+                    .body("{\"data\":{}}");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_ok.json"));
+            });
+
+            let got = client.fetch_raw_info().await;
+
+            mock.assert();
+
+            assert_eq!(got.unwrap(), expected())
+        }
+
+        #[tokio::test]
+        async fn returns_free_heap_if_reported() {
+            let (_server, client) = mock_client_returning("info", "response_info_heap.json");
+
+            let got = client.fetch_raw_info().await;
+
+            assert_eq!(
+                got.unwrap(),
+                RawInfo {
+                    free_heap: Some(24512),
+                    ..expected()
+                }
+            )
+        }
+    }
+
+    mod ip_mode {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::dhcp("response_info_dhcp.json", Some(IpMode::Dhcp))]
+        #[case::static_ip("response_info_static_ip.json", Some(IpMode::Static))]
+        #[case::not_reported("response_info_ok.json", None)]
+        #[tokio::test]
+        async fn returns_expected_result(#[case] fixture: &str, #[case] expected: Option<IpMode>) {
+            let (_server, client) = mock_client_returning("info", fixture);
+
+            let got = client.fetch_raw_info().await;
+
+            assert_eq!(got.unwrap().ip_mode, expected)
+        }
+    }
+
+    mod wifi_security {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_reported_mode() {
+            let (_server, client) =
+                mock_client_returning("info", "response_info_wifi_security.json");
+
+            let got = client.fetch_raw_info().await;
+
+            assert_eq!(got.unwrap().wifi_security, Some(WifiSecurity::Wpa2))
+        }
+
+        #[tokio::test]
+        async fn returns_none_if_not_reported() {
+            let (_server, client) = mock_client_returning("info", "response_info_ok.json");
+
+            let got = client.fetch_raw_info().await;
+
+            assert_eq!(got.unwrap().wifi_security, None)
+        }
+    }
+
+    mod reset_reason {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::known("response_info_reset_reason.json", Some(ResetReason::SoftwareWatchdog))]
+        #[case::unknown(
+            "response_info_reset_reason_unknown.json",
+            Some(ResetReason::Unknown("brownout".to_string()))
+        )]
+        #[case::not_reported("response_info_ok.json", None)]
+        #[tokio::test]
+        async fn returns_expected_result(
+            #[case] fixture: &str,
+            #[case] expected: Option<ResetReason>,
+        ) {
+            let (_server, client) = mock_client_returning("info", fixture);
+
+            let got = client.fetch_raw_info().await;
+
+            assert_eq!(got.unwrap().reset_reason, expected)
+        }
+
+        #[rstest]
+        #[case::power_on(ResetReason::PowerOn, false)]
+        #[case::software_restart(ResetReason::SoftwareRestart, false)]
+        #[case::hardware_watchdog(ResetReason::HardwareWatchdog, true)]
+        #[case::exception(ResetReason::Exception, true)]
+        #[case::software_watchdog(ResetReason::SoftwareWatchdog, true)]
+        #[case::unknown(ResetReason::Unknown("brownout".to_string()), false)]
+        fn detected_crash(#[case] reason: ResetReason, #[case] expected: bool) {
+            assert_eq!(reason.is_crash(), expected)
+        }
+    }
+
+    mod ntp_server {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::reported("response_info_ntp_server.json", Some("pool.ntp.org"))]
+        #[case::not_reported("response_info_ok.json", None)]
+        #[tokio::test]
+        async fn returns_expected_result(#[case] fixture: &str, #[case] expected: Option<&str>) {
+            let (_server, client) = mock_client_returning("info", fixture);
+
+            let got = client.fetch_raw_info().await;
+
+            assert_eq!(got.unwrap().ntp_server.as_deref(), expected)
+        }
+    }
+
+    mod power_watts {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_reported_power() {
+            let (_server, client) = mock_client_returning("info", "response_info_power.json");
+
+            let got = client.fetch_raw_info().await;
+
+            assert_eq!(got.unwrap().power_watts, Some(42.5))
+        }
+
+        #[tokio::test]
+        async fn returns_none_without_metering() {
+            let (_server, client) = mock_client_returning("info", "response_info_ok.json");
+
+            let got = client.fetch_raw_info().await;
+
+            assert_eq!(got.unwrap().power_watts, None)
+        }
+    }
+
+    mod button_presses {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_reported_presses() {
+            let (_server, client) =
+                mock_client_returning("info", "response_info_button_presses.json");
+
+            let got = client.fetch_raw_info().await.unwrap();
+
+            assert_eq!(got.button_press_count, Some(7));
+            assert_eq!(got.last_press_time, Some(1700000000));
+        }
+
+        #[tokio::test]
+        async fn returns_none_if_unsupported() {
+            let (_server, client) = mock_client_returning("info", "response_info_ok.json");
+
+            let got = client.fetch_raw_info().await.unwrap();
+
+            assert_eq!(got.button_press_count, None);
+            assert_eq!(got.last_press_time, None);
+        }
+    }
+
+    mod outlet_by_name {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::first("Lamp", Some(0))]
+        #[case::second("Heater", Some(1))]
+        #[case::not_labeled("Fan", None)]
+        #[tokio::test]
+        async fn returns_expected_result(#[case] name: &str, #[case] expected: Option<u8>) {
+            let (_server, client) =
+                mock_client_returning("info", "response_info_outlet_names.json");
+
+            let got = client.fetch_raw_info().await;
+
+            assert_eq!(got.unwrap().outlet_by_name(name), expected)
+        }
+
+        #[tokio::test]
+        async fn returns_none_without_labels() {
+            let (_server, client) = mock_client_returning("info", "response_info_ok.json");
+
+            let got = client.fetch_raw_info().await;
+
+            assert_eq!(got.unwrap().outlet_by_name("Lamp"), None)
+        }
+    }
+
+    mod pulse_width {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::milliseconds(
+            "response_info_pulse_width_ms.json",
+            PulseWidthUnit::Milliseconds,
+            Duration::from_millis(1500)
+        )]
+        #[case::string(
+            "response_info_pulse_width_string.json",
+            PulseWidthUnit::Milliseconds,
+            Duration::from_millis(1500)
+        )]
+        #[case::seconds(
+            "response_info_pulse_width_seconds.json",
+            PulseWidthUnit::Seconds,
+            Duration::from_secs(2)
+        )]
+        #[case::per_outlet(
+            "response_info_pulse_on.json",
+            PulseWidthUnit::Milliseconds,
+            Duration::from_millis(1500)
+        )]
+        #[tokio::test]
+        async fn returns_expected_result(
+            #[case] fixture: &str,
+            #[case] unit: PulseWidthUnit,
+            #[case] expected: Duration,
+        ) {
+            let server = MockServer::start();
+            let client = Client::builder(server.host(), server.port())
+                .pulse_width_unit(unit)
+                .build()
+                .unwrap();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture(fixture));
+            });
+
+            let got = client.fetch_pulse_width().await;
+
+            assert_eq!(got.unwrap(), Some(expected))
+        }
+
+        #[tokio::test]
+        async fn returns_none_if_not_reported() {
+            let (_server, client) =
+                mock_client_returning("info", "response_info_mixed_outlets.json");
+
+            let got = client.fetch_pulse_width().await;
+
+            assert_eq!(got.unwrap(), None)
+        }
+    }
+
+    mod supported_commands {
+        use super::*;
+        use rstest::rstest;
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/commands")
+                    .body("{\"data\":{}}");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_commands_ok.json"));
+            });
+
+            let got = client.fetch_supported_commands().await;
+
+            mock.assert();
+
+            assert_eq!(
+                got.unwrap(),
+                [
+                    "info",
+                    "switches",
+                    "startups",
+                    "pulse",
+                    "sledBrightness",
+                    "wifi"
+                ]
+            )
+        }
+
+        #[tokio::test]
+        async fn errored_in_expected_way() {
+            let (_server, client) = mock_client_returning("commands", "response_error.json");
+
+            let got = client.fetch_supported_commands().await;
+
+            assert_eq!(got.unwrap_err(), Error::WrongParameters(None))
+        }
+
+        #[rstest]
+        #[case::no_data("response_commands_no_data.json")]
+        #[case::no_commands("response_commands_no_commands.json")]
+        #[tokio::test]
+        async fn errored_if_commands_are_missing(#[case] fixture: &str) {
+            let (_server, client) = mock_client_returning("commands", fixture);
+
+            let got = client.fetch_supported_commands().await;
+
+            assert_eq!(
+                got.unwrap_err(),
+                Error::UnexpectedResponse("data is missing".to_string())
+            )
+        }
+    }
+
+    mod signal_strength {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/signal_strength")
+                    .body("{\"data\":{}}");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_signal_strength_ok.json"));
+            });
+
+            let got = client.fetch_signal_strength().await;
+
+            mock.assert();
+
+            assert_eq!(got.unwrap(), Rssi(-67))
+        }
+
+        #[tokio::test]
+        async fn errored_in_expected_way() {
+            let (_server, client) = mock_client_returning("signal_strength", "response_error.json");
+
+            let got = client.fetch_signal_strength().await;
+
+            assert_eq!(got.unwrap_err(), Error::WrongParameters(None))
+        }
+
+        #[tokio::test]
+        async fn errored_if_data_is_missing() {
+            let (_server, client) = mock_client_returning("signal_strength", "response_ok.json");
+
+            let got = client.fetch_signal_strength().await;
+
+            assert_eq!(
+                got.unwrap_err(),
+                Error::UnexpectedResponse("data is missing".to_string())
+            )
+        }
+    }
+
+    mod is_pairing {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::pairing("response_info_pairing.json", true)]
+        #[case::normal("response_info_not_pairing.json", false)]
+        #[case::not_reported("response_info_ok.json", false)]
+        #[tokio::test]
+        async fn returns_expected_result(#[case] fixture: &str, #[case] expected: bool) {
+            let (_server, client) = mock_client_returning("info", fixture);
+
+            let got = client.is_pairing().await;
+
+            assert_eq!(got.unwrap(), expected)
+        }
+    }
+
+    mod capabilities {
+        use super::*;
+
+        fn mock_info<'a>(server: &'a MockServer, fixture: &str) -> httpmock::Mock<'a> {
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture(fixture));
+            })
+        }
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let (server, client) = make_server_and_client();
+            mock_info(&server, "response_info_power.json");
+
+            let got = client.capabilities_cached().await;
+
+            assert_eq!(
+                got.unwrap(),
+                Capabilities {
+                    fw_version: Some("1.4.3".to_string()),
+                    multi_outlet: true,
+                    pulse: true,
+                    sled_brightness: false,
+                    power_metering: true,
+                }
+            )
+        }
+
+        #[tokio::test]
+        async fn not_refined_without_commands_list() {
+            let (server, client) = make_server_and_client();
+            mock_info(&server, "response_info_power.json");
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/commands");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_commands_no_commands.json"));
+            });
+
+            let got = client.capabilities_cached().await;
+
+            assert_eq!(
+                got.unwrap(),
+                Capabilities {
+                    fw_version: Some("1.4.3".to_string()),
+                    multi_outlet: true,
+                    pulse: true,
+                    sled_brightness: false,
+                    power_metering: true,
+                }
+            )
+        }
+
+        #[tokio::test]
+        async fn refined_with_supported_commands() {
+            let (server, client) = make_server_and_client();
+            mock_info(&server, "response_info_power.json");
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/commands");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_commands_ok.json"));
+            });
+
+            let got = client.capabilities_cached().await;
+
+            assert_eq!(
+                got.unwrap(),
+                Capabilities {
+                    fw_version: Some("1.4.3".to_string()),
+                    multi_outlet: true,
+                    pulse: true,
+                    sled_brightness: true,
+                    power_metering: true,
+                }
+            )
+        }
+
+        #[tokio::test]
+        async fn probed_only_once() {
+            let (server, client) = make_server_and_client();
+            let mock = mock_info(&server, "response_info_ok.json");
+
+            for _ in 0..3 {
+                client.capabilities_cached().await.unwrap();
+            }
+
+            mock.assert_hits(1);
+        }
+
+        #[tokio::test]
+        async fn probed_again_on_refresh() {
+            let (server, client) = make_server_and_client();
+            let mock = mock_info(&server, "response_info_ok.json");
+
+            client.capabilities_cached().await.unwrap();
+            client.refresh_capabilities().await.unwrap();
+            client.capabilities_cached().await.unwrap();
+
+            mock.assert_hits(2);
+        }
+
+        #[tokio::test]
+        async fn probed_again_on_firmware_change() {
+            let (server, client) = make_server_and_client();
+            let mut mock = mock_info(&server, "response_info_ok.json");
+            client.capabilities_cached().await.unwrap();
+            mock.delete();
+            let mock = mock_info(&server, "response_info_fw_upgraded.json");
+
+            client.fetch_raw_info().await.unwrap();
+            let got = client.capabilities_cached().await;
+
+            mock.assert_hits(2);
+            assert!(got.unwrap().sled_brightness);
+        }
+
+        #[tokio::test]
+        async fn probed_again_on_firmware_change_seen_by_lenient_fetch() {
+            let (server, client) = make_server_and_client();
+            let mut mock = mock_info(&server, "response_info_ok.json");
+            client.capabilities_cached().await.unwrap();
+            mock.delete();
+            let mock = mock_info(&server, "response_info_fw_upgraded.json");
+
+            client.fetch_info_lenient().await.unwrap();
+            let got = client.capabilities_cached().await;
+
+            mock.assert_hits(2);
+            assert!(got.unwrap().sled_brightness);
+        }
+    }
+
+    mod snapshot {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let (_server, client) =
+                mock_client_returning("info", "response_info_mixed_outlets.json");
+
+            let got = client.fetch_snapshot().await;
+
+            assert_eq!(
+                got.unwrap(),
+                vec![
+                    OutletSnapshot {
+                        outlet: 0,
+                        switch: SwitchPosition::On,
+                        startup: StartupPosition::Stay,
+                    },
+                    OutletSnapshot {
+                        outlet: 1,
+                        switch: SwitchPosition::Off,
+                        startup: StartupPosition::Off,
+                    },
+                    OutletSnapshot {
+                        outlet: 2,
+                        switch: SwitchPosition::On,
+                        startup: StartupPosition::On,
+                    },
+                    OutletSnapshot {
+                        outlet: 3,
+                        switch: SwitchPosition::Off,
+                        startup: StartupPosition::Stay,
+                    },
+                ]
+            )
+        }
+    }
+
+    mod faults {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_reported_faults() {
+            let (_server, client) = mock_client_returning("info", "response_info_faults.json");
+
+            let got = client.fetch_faults().await;
+
+            assert_eq!(
+                got.unwrap(),
+                Faults {
+                    count: Some(3),
+                    last_error: Some("wdt reset".to_string()),
+                }
+            )
+        }
+
+        #[tokio::test]
+        async fn returns_none_if_not_reported() {
+            let (_server, client) = mock_client_returning("info", "response_info_ok.json");
+
+            let got = client.fetch_faults().await;
+
+            assert_eq!(
+                got.unwrap(),
+                Faults {
+                    count: None,
+                    last_error: None,
+                }
+            )
+        }
+    }
+
+    #[cfg(feature = "ota")]
+    mod verify_firmware {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let (_server, client) = mock_client_returning("info", "response_info_ok.json");
+
+            let got = client
+                .verify_firmware("1.4.3", Duration::from_secs(1))
+                .await;
+
+            assert!(got.is_ok());
+        }
+
+        #[tokio::test]
+        async fn errored_on_mismatch() {
+            let (_server, client) = mock_client_returning("info", "response_info_ok.json");
+
+            let got = client
+                .verify_firmware("3.5.0", Duration::from_secs(1))
+                .await;
+
+            assert_eq!(
+                got.unwrap_err(),
+                Error::FirmwareMismatch {
+                    expected: "3.5.0".to_string(),
+                    actual: Some("1.4.3".to_string()),
+                }
+            )
+        }
+
+        #[tokio::test]
+        async fn errored_if_device_is_unreachable() {
+            let client = Client::new("127.0.0.1", 1);
+
+            let got = client
+                .verify_firmware("1.4.3", Duration::from_millis(100))
+                .await;
+
+            assert!(matches!(got.unwrap_err(), Error::Http(_)));
+        }
+
+        #[tokio::test]
+        async fn waited_for_expected_version() {
+            let (server, client) = make_server_and_client();
+            let old = server
+                .mock_async(|when, then| {
+                    when.method("POST").path("/zeroconf/info");
+                    then.status(200)
+                        .header("content-type", "application/json; charset=utf-8")
+                        .body(load_fixture("response_info_ok.json"));
+                })
+                .await;
+            let upgrade = async {
+                runtime::sleep(Duration::from_millis(200)).await;
+                old.delete_async().await;
+                server
+                    .mock_async(|when, then| {
+                        when.method("POST").path("/zeroconf/info");
+                        then.status(200)
+                            .header("content-type", "application/json; charset=utf-8")
+                            .body(load_fixture("response_info_fw_upgraded.json"));
+                    })
+                    .await
+            };
+
+            let (got, new) = tokio::join!(
+                client.verify_firmware("1.5.0", Duration::from_secs(2)),
+                upgrade
+            );
+
+            new.assert_async().await;
+
+            assert!(got.is_ok());
+        }
+
+        #[tokio::test]
+        async fn errored_if_device_is_hanging() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200).delay(Duration::from_secs(5));
+            });
+
+            let got = client
+                .verify_firmware("1.4.3", Duration::from_millis(100))
+                .await;
+
+            assert_eq!(
+                got.unwrap_err(),
+                Error::TimedOut(Duration::from_millis(100))
+            )
+        }
+    }
+
+    mod set_pulse {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::unspecified(None, "request_pulse_ok.json")]
+        #[case::delay_off(Some(PulseMode::DelayOff), "request_pulse_delay_off.json")]
+        #[case::delay_on(Some(PulseMode::DelayOn), "request_pulse_delay_on.json")]
+        #[tokio::test]
+        async fn sent_expected_request(#[case] mode: Option<PulseMode>, #[case] fixture: &str) {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/pulse")
+                    .body(load_fixture(fixture));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client
+                .set_pulse(
+                    outlet(0),
+                    PulsePosition::On,
+                    Duration::from_millis(1500),
+                    mode,
+                )
+                .await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[tokio::test]
+        async fn targeted_given_outlet() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/pulse")
+                    .body(load_fixture("request_pulse_outlet_2.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client
+                .set_pulse(
+                    outlet(2),
+                    PulsePosition::On,
+                    Duration::from_secs(36_000),
+                    None,
+                )
+                .await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[rstest]
+        #[case::too_short(
+            Duration::ZERO,
+            "pulse width should be a multiple of 500ms within 500..=36000000ms, got 0ms"
+        )]
+        #[case::too_long(
+            Duration::from_millis(36_000_500),
+            "pulse width should be a multiple of 500ms within 500..=36000000ms, got 36000500ms"
+        )]
+        #[case::not_multiple(
+            Duration::from_millis(1200),
+            "pulse width should be a multiple of 500ms within 500..=36000000ms, got 1200ms"
+        )]
+        #[case::sub_millisecond(
+            Duration::from_micros(1_000_500),
+            "pulse width should be a multiple of 500ms within 500..=36000000ms, got 1000.5ms"
+        )]
+        #[tokio::test]
+        async fn errored_in_expected_way(#[case] width: Duration, #[case] expected: &str) {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.any_request();
+                then.status(200);
+            });
+
+            let got = client
+                .set_pulse(outlet(0), PulsePosition::On, width, None)
+                .await;
+
+            mock.assert_hits(0);
+
+            assert_eq!(
+                got.unwrap_err(),
+                Error::InvalidParameter(expected.to_string())
+            )
+        }
+    }
+
+    mod cancel_pulse {
+        use super::*;
+
+        #[tokio::test]
+        async fn sent_expected_request() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/pulse")
+                    .body(load_fixture("request_pulse_cancel.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.cancel_pulse().await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[tokio::test]
+        async fn returns_ok_if_nothing_to_cancel() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/pulse");
+                then.status(409)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_error_409.json"));
+            });
+
+            let got = client.cancel_pulse().await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[tokio::test]
+        async fn errored_in_expected_way() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/pulse");
+                then.status(400)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_error.json"));
+            });
+
+            let got = client.cancel_pulse().await;
+
+            assert_eq!(got.unwrap_err(), Error::WrongParameters(None));
+        }
+    }
+
+    mod set_startup_position {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::integer("response_ok.json")]
+        #[case::string("response_ok_error_string.json")]
+        #[case::float("response_ok_error_float.json")]
+        #[tokio::test]
+        async fn accepts_error_code_forms(#[case] fixture: &str) {
+            let (_server, client) = mock_client_returning("startups", fixture);
+
+            let got = client.set_startup_position(StartupPosition::Stay).await;
+
+            assert!(got.is_ok());
+        }
+
+        #[tokio::test]
+        async fn sent_expected_request() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/startups")
+                    .body(load_fixture("request_startups_ok.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.set_startup_position(StartupPosition::Stay).await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[tokio::test]
+        async fn errored_in_expected_way() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/startups")
+                    .body(load_fixture("request_startups_ok.json"));
+                then.status(400)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_error.json"));
+            });
+
+            let got = client.set_startup_position(StartupPosition::Stay).await;
+
+            mock.assert();
+
+            assert!(got.is_err());
+            assert_eq!(got.unwrap_err(), Error::WrongParameters(None))
+        }
+    }
+
+    mod set_startup_positions {
+        use super::*;
+        use rstest::rstest;
+
+        #[tokio::test]
+        async fn sent_expected_request() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/startups")
+                    .body(load_fixture("request_startups_mixed.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client
+                .set_startup_positions(&[
+                    (outlet(1), StartupPosition::On),
+                    (outlet(3), StartupPosition::Stay),
+                ])
+                .await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[rstest]
+        #[case::empty(&[], "configs must not be empty")]
+        #[case::duplicate(
+            &[
+                (outlet(1), StartupPosition::On),
+                (outlet(2), StartupPosition::Off),
+                (outlet(1), StartupPosition::Stay),
+            ],
+            "duplicate outlet 1"
+        )]
+        #[tokio::test]
+        async fn errored_in_expected_way(
+            #[case] configs: &[(OutletIndex, StartupPosition)],
+            #[case] expected: &str,
+        ) {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.any_request();
+                then.status(200);
+            });
+
+            let got = client.set_startup_positions(configs).await;
+
+            mock.assert_hits(0);
+
+            assert_eq!(
+                got.unwrap_err(),
+                Error::InvalidParameter(expected.to_string())
+            )
+        }
+    }
+
+    mod void_commands {
+        use super::*;
+        use rstest::rstest;
+
+        enum Command {
+            Startups,
+            StartupPositions,
+            Switches,
+            SwitchPositions,
+            SledBrightness,
+            StatusLed,
+            Pulse,
+            CancelPulse,
+            #[cfg(feature = "ota")]
+            OtaUnlock,
+            #[cfg(feature = "ota")]
+            OtaFlash,
+            TurnOn,
+            TurnOff,
+        }
+
+        impl Command {
+            fn endpoint(&self) -> &str {
+                match self {
+                    Command::Startups | Command::StartupPositions => "startups",
+                    Command::Switches
+                    | Command::SwitchPositions
+                    | Command::TurnOn
+                    | Command::TurnOff => "switches",
+                    Command::SledBrightness => "sledBrightness",
+                    Command::StatusLed => "sledonline",
+                    Command::Pulse | Command::CancelPulse => "pulse",
+                    #[cfg(feature = "ota")]
+                    Command::OtaUnlock => "ota_unlock",
+                    #[cfg(feature = "ota")]
+                    Command::OtaFlash => "ota_flash",
+                }
+            }
+
+            async fn call(&self, client: &Client) -> Result<()> {
+                match self {
+                    Command::Startups => client.set_startup_position(StartupPosition::Stay).await,
+                    Command::StartupPositions => {
+                        client
+                            .set_startup_positions(&[(outlet(0), StartupPosition::Stay)])
+                            .await
+                    }
+                    Command::Switches => client.set_switch_position(SwitchPosition::On).await,
+                    Command::SwitchPositions => {
+                        client
+                            .set_switch_positions(&[(outlet(0), SwitchPosition::On)])
+                            .await
+                    }
+                    Command::TurnOn => client.turn_on().await,
+                    Command::TurnOff => client.turn_off().await,
+                    Command::SledBrightness => client.set_sled_brightness(30).await,
+                    Command::StatusLed => client.set_status_led(false).await,
+                    Command::Pulse => {
+                        client
+                            .set_pulse(
+                                outlet(0),
+                                PulsePosition::On,
+                                Duration::from_millis(1500),
+                                None,
+                            )
+                            .await
+                    }
+                    Command::CancelPulse => client.cancel_pulse().await,
+                    #[cfg(feature = "ota")]
+                    Command::OtaUnlock => client.ota_unlock("1000abcdef").await,
+                    #[cfg(feature = "ota")]
+                    Command::OtaFlash => client.ota_flash(FIRMWARE_URL, FIRMWARE_SHA256).await,
+                }
+            }
+        }
+
+        #[rstest]
+        #[case::set_startup_position(Command::Startups)]
+        #[case::set_startup_positions(Command::StartupPositions)]
+        #[case::set_switch_position(Command::Switches)]
+        #[case::set_switch_positions(Command::SwitchPositions)]
+        #[case::set_sled_brightness(Command::SledBrightness)]
+        #[case::set_status_led(Command::StatusLed)]
+        #[case::set_pulse(Command::Pulse)]
+        #[case::cancel_pulse(Command::CancelPulse)]
+        #[cfg_attr(feature = "ota", case::ota_unlock(Command::OtaUnlock))]
+        #[cfg_attr(feature = "ota", case::ota_flash(Command::OtaFlash))]
+        #[case::turn_on(Command::TurnOn)]
+        #[case::turn_off(Command::TurnOff)]
+        #[tokio::test]
+        async fn returns_expected_result(#[case] command: Command) {
+            let (_server, client) = mock_client_returning(command.endpoint(), "response_ok.json");
+
+            let got = command.call(&client).await;
+
+            assert!(got.is_ok());
+        }
+
+        #[rstest]
+        #[case::set_startup_position(Command::Startups)]
+        #[case::set_startup_positions(Command::StartupPositions)]
+        #[case::set_switch_position(Command::Switches)]
+        #[case::set_switch_positions(Command::SwitchPositions)]
+        #[case::set_sled_brightness(Command::SledBrightness)]
+        #[case::set_status_led(Command::StatusLed)]
+        #[case::set_pulse(Command::Pulse)]
+        #[case::cancel_pulse(Command::CancelPulse)]
+        #[cfg_attr(feature = "ota", case::ota_unlock(Command::OtaUnlock))]
+        #[cfg_attr(feature = "ota", case::ota_flash(Command::OtaFlash))]
+        #[case::turn_on(Command::TurnOn)]
+        #[case::turn_off(Command::TurnOff)]
+        #[tokio::test]
+        async fn errored_in_expected_way(#[case] command: Command) {
+            let (_server, client) =
+                mock_client_returning(command.endpoint(), "response_error.json");
+
+            let got = command.call(&client).await;
+
+            assert_eq!(got.unwrap_err(), Error::WrongParameters(None))
+        }
+    }
+
+    mod set_sled_brightness {
+        use super::*;
+
+        #[tokio::test]
+        async fn sent_expected_request() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/sledBrightness")
+                    .body(load_fixture("request_sled_brightness_ok.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.set_sled_brightness(30).await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[tokio::test]
+        async fn errored_on_out_of_range_level() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST");
+                then.status(200);
+            });
+
+            let got = client.set_sled_brightness(101).await;
+
+            mock.assert_hits(0);
+
+            assert!(matches!(got.unwrap_err(), Error::InvalidParameter(_)));
+        }
+
+        #[tokio::test]
+        async fn returns_reported_brightness() {
+            let (_server, client) =
+                mock_client_returning("info", "response_info_sled_brightness.json");
+
+            let got = client.fetch_raw_info().await;
+
+            assert_eq!(got.unwrap().sled_brightness, Some(30))
+        }
+    }
+
+    #[cfg(feature = "ota")]
+    mod ota_unlock {
+        use super::*;
+        use rstest::rstest;
+
+        #[tokio::test]
+        async fn sent_expected_request() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/ota_unlock")
+                    .body(load_fixture("request_ota_unlock_ok.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.ota_unlock("1000abcdef").await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[rstest]
+        #[case::wrong_device_id("response_error_404.json", Error::InvalidDeviceId("1000abcdef".to_string()))]
+        #[case::failed("response_error_500.json", Error::OtaUnlockFailed(500))]
+        #[tokio::test]
+        async fn errored_in_expected_way(#[case] fixture: &str, #[case] expected: Error) {
+            let (_server, client) = mock_client_returning("ota_unlock", fixture);
+
+            let got = client.ota_unlock("1000abcdef").await;
+
+            assert_eq!(got.unwrap_err(), expected)
+        }
+    }
+
+    #[cfg(feature = "ota")]
+    mod ota_flash {
+        use super::*;
+        use rstest::rstest;
+
+        #[tokio::test]
+        async fn sent_expected_request() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/ota_flash")
+                    .body(load_fixture("request_ota_flash_ok.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.ota_flash(FIRMWARE_URL, FIRMWARE_SHA256).await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[rstest]
+        #[case::locked("response_error_403.json", Error::OtaLocked)]
+        #[case::download_timed_out("response_error_408.json", Error::FirmwareDownloadTimedOut)]
+        #[case::too_large("response_error_413.json", Error::FirmwareTooLarge)]
+        #[case::download_failed("response_error_424.json", Error::FirmwareDownloadFailed)]
+        #[case::checksum_mismatch("response_error_471.json", Error::FirmwareChecksumMismatch)]
+        #[case::wrong_parameters("response_error.json", Error::WrongParameters(None))]
+        #[tokio::test]
+        async fn errored_in_expected_way(#[case] fixture: &str, #[case] expected: Error) {
+            let (_server, client) = mock_client_returning("ota_flash", fixture);
+
+            let got = client.ota_flash(FIRMWARE_URL, FIRMWARE_SHA256).await;
+
+            assert_eq!(got.unwrap_err(), expected)
+        }
+
+        #[rstest]
+        #[case::empty("")]
+        #[case::too_short(&FIRMWARE_SHA256[1..])]
+        #[case::not_hex(&FIRMWARE_SHA256.replace('a', "g"))]
+        #[tokio::test]
+        async fn errored_on_invalid_sha256(#[case] sha256sum: &str) {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.any_request();
+                then.status(200);
+            });
+
+            let got = client.ota_flash(FIRMWARE_URL, sha256sum).await;
+
+            mock.assert_hits(0);
+
+            assert_eq!(
+                got.unwrap_err(),
+                Error::InvalidSha256(sha256sum.to_string())
+            )
+        }
+    }
+
+    mod set_status_led {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::on(true, "request_sledonline_on.json")]
+        #[case::off(false, "request_sledonline_off.json")]
+        #[tokio::test]
+        async fn sent_expected_request(#[case] on: bool, #[case] fixture: &str) {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/sledonline")
+                    .body(load_fixture(fixture));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.set_status_led(on).await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+    }
+
+    mod status_led {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::on("response_info_ok.json", true)]
+        #[case::off("response_info_sled_offline.json", false)]
+        #[case::not_reported("response_info_no_sled_online.json", true)]
+        #[tokio::test]
+        async fn returns_expected_result(#[case] fixture: &str, #[case] expected: bool) {
+            let (_server, client) = mock_client_returning("info", fixture);
+
+            let got = client.fetch_info().await;
+
+            assert_eq!(got.unwrap().status_led, expected)
+        }
+    }
+
+    #[cfg(feature = "wifi")]
+    mod set_wifi {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/wifi")
+                    .body(load_fixture("request_wifi_ok.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.set_wifi("home", "secret").await;
+
+            mock.assert();
+
+            assert_eq!(got.unwrap(), WifiSetResult::Accepted)
+        }
+
+        #[tokio::test]
+        async fn returns_rejection() {
+            let (_server, client) = mock_client_returning("wifi", "response_error.json");
+
+            let got = client.set_wifi("home", "secret").await;
+
+            assert_eq!(
+                got.unwrap(),
+                WifiSetResult::Rejected(Error::WrongParameters(None))
+            )
+        }
+
+        #[tokio::test]
+        async fn accepted_on_connection_dropped_after_ok() {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            use tokio::net::TcpListener;
+
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let server = tokio::spawn(async move {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0; 4096];
+                let _ = stream.read(&mut buf).await.unwrap();
+
+                // Body is cut off, as if device disconnected while responding
+                stream
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\n\
+                          Content-Type: application/json; charset=utf-8\r\n\
+                          Content-Length: 27\r\n\r\n{ \"seq\"",
+                    )
+                    .await
+                    .unwrap();
+            });
+            let client = Client::new("127.0.0.1", port);
+
+            let got = client.set_wifi("home", "secret").await;
+            server.await.unwrap();
+
+            assert_eq!(got.unwrap(), WifiSetResult::Accepted)
+        }
+
+        #[tokio::test]
+        async fn errored_on_empty_ssid() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.any_request();
+                then.status(200);
+            });
+
+            let got = client.set_wifi("", "secret").await;
+
+            mock.assert_hits(0);
+
+            assert_eq!(
+                got.unwrap_err(),
+                Error::InvalidParameter("ssid must not be empty".to_string())
+            )
+        }
+    }
+
+    mod target_device {
+        use super::*;
+
+        #[tokio::test]
+        async fn sent_device_id() {
+            let server = MockServer::start();
+            let client = Client::builder(server.host(), server.port())
+                .target_device("1000abcdef")
+                .build()
+                .unwrap();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/switches")
+                    .header(DEVICE_ID_HEADER, "1000abcdef")
+                    .body(load_fixture("request_switches_ok.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.set_switch_position(SwitchPosition::On).await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+    }
+
+    mod auth {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::basic(|v: ClientBuilder| v.basic_auth("user", "pass"), "Basic dXNlcjpwYXNz")]
+        #[case::bearer(|v: ClientBuilder| v.bearer_auth("token"), "Bearer token")]
+        #[case::last_wins(
+            |v: ClientBuilder| v.bearer_auth("token").basic_auth("user", "pass"),
+            "Basic dXNlcjpwYXNz"
+        )]
+        #[tokio::test]
+        async fn sent_authorization_header(
+            #[case] configure: fn(ClientBuilder) -> ClientBuilder,
+            #[case] expected: &str,
+        ) {
+            let server = MockServer::start();
+            let client = configure(Client::builder(server.host(), server.port()))
+                .build()
+                .unwrap();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/info")
+                    .header("authorization", expected);
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_ok.json"));
+            });
+
+            let got = client.fetch_info().await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+    }
+
+    mod redirect_policy {
+        use super::*;
+        use rstest::rstest;
+
+        fn mock_redirect(server: &MockServer, status: u16) -> httpmock::Mock<'_> {
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/switches");
+                then.status(status)
+                    .header("location", "/proxied/zeroconf/switches");
+            })
+        }
+
+        fn mock_proxied(server: &MockServer) -> httpmock::Mock<'_> {
+            server.mock(|when, then| {
+                when.method("POST")
+                    .path("/proxied/zeroconf/switches")
+                    .body(load_fixture("request_switches_ok.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            })
+        }
+
+        #[rstest]
+        #[case::temporary(307)]
+        #[case::permanent(308)]
+        #[tokio::test]
+        async fn preserved_body_by_default(#[case] status: u16) {
+            let (server, client) = make_server_and_client();
+            let redirect = mock_redirect(&server, status);
+            let proxied = mock_proxied(&server);
+
+            let got = client.set_switch_position(SwitchPosition::On).await;
+
+            redirect.assert();
+            proxied.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[rstest]
+        #[case::moved_permanently(301)]
+        #[case::found(302)]
+        #[case::see_other(303)]
+        #[tokio::test]
+        async fn not_followed_changing_method_by_default(#[case] status: u16) {
+            let (server, client) = make_server_and_client();
+            let redirect = mock_redirect(&server, status);
+            let proxied = mock_proxied(&server);
+
+            let got = client.set_switch_position(SwitchPosition::On).await;
+
+            redirect.assert();
+            proxied.assert_hits(0);
+
+            assert!(got.is_err());
+        }
+
+        #[tokio::test]
+        async fn used_given_policy() {
+            let server = MockServer::start();
+            let client = Client::builder(server.host(), server.port())
+                .redirect_policy(Policy::none())
+                .build()
+                .unwrap();
+            let redirect = mock_redirect(&server, 308);
+            let proxied = mock_proxied(&server);
+
+            let got = client.set_switch_position(SwitchPosition::On).await;
+
+            redirect.assert();
+            proxied.assert_hits(0);
+
+            assert!(got.is_err());
+        }
+    }
+
+    mod from_addr {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::ipv4("192.168.1.75:8080", "192.168.1.75", 8080)]
+        #[case::ipv4_without_port("192.168.1.75", "192.168.1.75", 8081)]
+        #[case::hostname("sonoff.local:8080", "sonoff.local", 8080)]
+        #[case::hostname_without_port("sonoff.local", "sonoff.local", 8081)]
+        #[case::ipv6("[fe80::1]:8080", "fe80::1", 8080)]
+        #[case::ipv6_without_port("fe80::1", "fe80::1", 8081)]
+        #[case::bracketed_ipv6_without_port("[fe80::1]", "fe80::1", 8081)]
+        fn returns_expected_result(#[case] addr: &str, #[case] host: &str, #[case] port: u16) {
+            let got = Client::from_addr(addr).unwrap();
+
+            assert_eq!(got.effective_config()["host"], host);
+            assert_eq!(got.effective_config()["port"], port);
+        }
+
+        #[rstest]
+        #[case::empty("")]
+        #[case::empty_host(":8081")]
+        #[case::invalid_port("192.168.1.75:http")]
+        #[case::port_out_of_range("192.168.1.75:65536")]
+        #[case::url("http://192.168.1.75:8081")]
+        #[case::whitespace("sonoff local:8081")]
+        fn errored_in_expected_way(#[case] addr: &str) {
+            let got = Client::from_addr(addr);
+
+            assert_eq!(
+                got.err().unwrap(),
+                Error::InvalidParameter(format!("invalid address {}", addr))
+            );
+        }
+
+        #[test]
+        fn errored_on_zero_port() {
+            let got = Client::from_addr("192.168.1.75:0");
+
+            assert_eq!(
+                got.err().unwrap(),
+                Error::InvalidParameter("port must not be 0".to_string())
+            );
+        }
+
+        #[test]
+        fn constructed_from_socket_addr() {
+            let addr: SocketAddr = "192.168.1.75:8080".parse().unwrap();
+
+            let got = Client::try_from(addr).unwrap();
+
+            assert_eq!(got.url("info"), "http://192.168.1.75:8080/zeroconf/info");
+        }
+
+        #[test]
+        fn errored_on_socket_addr_with_zero_port() {
+            let addr: SocketAddr = "192.168.1.75:0".parse().unwrap();
+
+            let got = Client::try_from(addr);
+
+            assert_eq!(
+                got.err().unwrap(),
+                Error::InvalidParameter("port must not be 0".to_string())
+            );
+        }
+
+        #[test]
+        fn errored_on_scoped_socket_addr() {
+            let addr: SocketAddr = "[fe80::1%2]:8081".parse().unwrap();
+
+            let got = Client::try_from(addr);
+
+            assert_eq!(
+                got.err().unwrap(),
+                Error::InvalidParameter("scoped IPv6 address fe80::1%2 is unsupported".to_string())
+            );
+        }
+
+        #[rstest]
+        #[case::named_zone("fe80::1%eth0")]
+        #[case::numeric_zone("[fe80::1%2]:8081")]
+        fn errored_on_scoped_ipv6(#[case] addr: &str) {
+            let got = Client::from_addr(addr);
+
+            assert_eq!(
+                got.err().unwrap(),
+                Error::InvalidParameter(format!("scoped IPv6 address {} is unsupported", addr))
+            );
+        }
+    }
+
+    mod debug {
+        use super::*;
+
+        #[test]
+        fn printed_host_and_port_only() {
+            let client = Client::builder("127.0.0.1", 8081)
+                .basic_auth("admin", "secret")
+                .build()
+                .unwrap();
+
+            let got = format!("{:?}", client);
+
+            assert_eq!(got, "Client { host: \"127.0.0.1\", port: 8081, .. }")
+        }
+    }
+
+    mod effective_config {
+        use super::*;
+
+        #[test]
+        fn returns_expected_result() {
+            let client = Client::builder("192.168.1.75", 8081)
+                .retries(2)
+                .connect_timeout(Duration::from_secs(2))
+                .serialize_requests(true)
+                .default_outlets(&[outlet(1), outlet(3)])
+                .build()
+                .unwrap();
+            client.set_timeout(Some(Duration::from_millis(1500)));
+
+            let got = client.effective_config();
+
+            assert_eq!(got["host"], "192.168.1.75");
+            assert_eq!(got["port"], 8081);
+            assert_eq!(got["scheme"], "http");
+            assert_eq!(got["encrypted"], false);
+            assert_eq!(got["timeout_ms"], 1500);
+            assert_eq!(got["connect_timeout_ms"], 2000);
+            assert_eq!(got["retries"], 2);
+            assert_eq!(got["serialize_requests"], true);
+            assert_eq!(got["default_outlets"], serde_json::json!([1, 3]));
+            assert_eq!(got["auth"], serde_json::Value::Null);
+        }
+
+        #[test]
+        fn redacted_credentials() {
+            let basic = Client::builder("192.168.1.75", 8081)
+                .basic_auth("user", "s3cret")
+                .build()
+                .unwrap();
+            let bearer = Client::builder("192.168.1.75", 8081)
+                .bearer_auth("t0ken")
+                .build()
+                .unwrap();
+
+            let basic = basic.effective_config();
+            let bearer = bearer.effective_config();
+
+            assert_eq!(
+                basic["auth"],
+                serde_json::json!({"type": "basic", "user": "user", "password": "<redacted>"})
+            );
+            assert_eq!(
+                bearer["auth"],
+                serde_json::json!({"type": "bearer", "token": "<redacted>"})
+            );
+            assert!(!basic.to_string().contains("s3cret"));
+            assert!(!bearer.to_string().contains("t0ken"));
+        }
+    }
+
+    mod compress_requests {
+        use super::*;
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        #[tokio::test]
+        async fn sent_compressed_body() {
+            let server = MockServer::start();
+            let client = Client::builder(server.host(), server.port())
+                .compress_requests(true)
+                .build()
+                .unwrap();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/switches")
+                    .header("content-encoding", "gzip")
+                    .matches(|req| {
+                        let mut body = String::new();
+                        GzDecoder::new(req.body.as_deref().unwrap_or_default())
+                            .read_to_string(&mut body)
+                            .is_ok()
+                            && body == load_fixture("request_switches_ok.json")
+                    });
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.set_switch_position(SwitchPosition::On).await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+    }
+
+    mod retries {
+        use super::*;
+        use std::time::Instant;
+
+        #[tokio::test]
+        async fn respected_retry_after() {
+            let server = MockServer::start_async().await;
+            let client = Client::builder(server.host(), server.port())
+                .retries(1)
+                .build()
+                .unwrap();
+            let busy_mock = server
+                .mock_async(|when, then| {
+                    when.method("POST").path("/zeroconf/switches");
+                    then.status(503).header("retry-after", "1");
+                })
+                .await;
+            let started = Instant::now();
+
+            let request = tokio::spawn(async move {
+                let got = client.set_switch_position(SwitchPosition::On).await;
+                (got, started.elapsed())
+            });
+            while busy_mock.hits_async().await == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            busy_mock.delete_async().await;
+            let ok_mock = server
+                .mock_async(|when, then| {
+                    when.method("POST").path("/zeroconf/switches");
+                    then.status(200)
+                        .header("content-type", "application/json; charset=utf-8")
+                        .body(load_fixture("response_ok.json"));
+                })
+                .await;
+            let (got, elapsed) = request.await.unwrap();
+
+            ok_mock.assert_async().await;
+
+            assert!(got.is_ok());
+            assert!(elapsed >= Duration::from_secs(1));
+        }
+
+        #[tokio::test]
+        async fn returns_expected_result_after_failures() {
+            use tokio::io::AsyncWriteExt;
+            use tokio::net::TcpListener;
+
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            // Connection is dropped without response twice, then device responds
+            let server = tokio::spawn(async move {
+                let mut started = vec![];
+                for i in 0..3 {
+                    let (mut stream, _) = listener.accept().await.unwrap();
+                    read_request(&mut stream).await;
+                    started.push(Instant::now());
+                    if i < 2 {
+                        continue;
+                    }
+                    let body = load_fixture("response_ok.json");
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\n\
+                         Content-Type: application/json; charset=utf-8\r\n\
+                         Content-Length: {}\r\n\
+                         Connection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    stream.write_all(response.as_bytes()).await.unwrap();
+                }
+                started
+            });
+            let client = Client::builder("127.0.0.1", port)
+                .retries(2)
+                .retry_delay(Duration::from_millis(100))
+                .build()
+                .unwrap();
+
+            let got = client.set_switch_position(SwitchPosition::On).await;
+            let started = server.await.unwrap();
+
+            assert!(got.is_ok());
+            assert!(started[1] - started[0] >= Duration::from_millis(100));
+            assert!(started[2] - started[1] >= Duration::from_millis(200));
+        }
+
+        #[tokio::test]
+        async fn not_retried_on_api_error() {
+            let server = MockServer::start();
+            let mock = server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/switches");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_error.json"));
+            });
+            let client = Client::builder(server.host(), server.port())
+                .retries(2)
+                .build()
+                .unwrap();
+
+            let got = client.set_switch_position(SwitchPosition::On).await;
+
+            mock.assert_hits(1);
+
+            assert_eq!(got.unwrap_err(), Error::WrongParameters(None));
+        }
+
+        #[test]
+        fn doubled_backoff() {
+            let base = Duration::from_millis(500);
+
+            let got: Vec<_> = (0..4).map(|v| backoff(base, v)).collect();
+
+            assert_eq!(
+                got,
+                [500, 1000, 2000, 4000].map(Duration::from_millis).to_vec()
+            );
+            assert_eq!(backoff(base, 64), base * u32::MAX);
+        }
+
+        #[test]
+        fn exposed_configuration() {
+            let client = Client::builder("127.0.0.1", 8081)
+                .retries(3)
+                .retry_delay(Duration::from_millis(250))
+                .build()
+                .unwrap();
+
+            assert_eq!(client.retries(), 3);
+            assert_eq!(client.retry_delay(), Duration::from_millis(250));
+        }
+
+        #[tokio::test]
+        async fn not_retried_by_default() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/switches");
+                then.status(503).header("retry-after", "1");
+            });
+
+            let got = client.set_switch_position(SwitchPosition::On).await;
+
+            mock.assert_hits(1);
+
+            assert_eq!(got.unwrap_err(), Error::HttpStatus(503));
+        }
+    }
+
+    mod sequence_commands {
+        use super::*;
+        use tokio::io::AsyncWriteExt;
+        use tokio::net::TcpListener;
+
+        fn sequence_of(request: &str) -> String {
+            let (_, body) = request.split_once("\r\n\r\n").unwrap();
+            let body: serde_json::Value = serde_json::from_str(body).unwrap();
+            body["sequence"].as_str().unwrap().to_string()
+        }
+
+        #[tokio::test]
+        async fn reused_sequence_on_retries() {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            // Device is busy on first attempt of every command
+            let server = tokio::spawn(async move {
+                let mut requests = vec![];
+                for i in 0..4 {
+                    let (mut stream, _) = listener.accept().await.unwrap();
+                    requests.push(read_request(&mut stream).await);
+                    let response = if i % 2 == 0 {
+                        "HTTP/1.1 503 Service Unavailable\r\n\
+                         Retry-After: 0\r\n\
+                         Content-Length: 0\r\n\
+                         Connection: close\r\n\r\n"
+                            .to_string()
+                    } else {
+                        let body = load_fixture("response_ok.json");
+                        format!(
+                            "HTTP/1.1 200 OK\r\n\
+                             Content-Type: application/json; charset=utf-8\r\n\
+                             Content-Length: {}\r\n\
+                             Connection: close\r\n\r\n{}",
+                            body.len(),
+                            body
+                        )
+                    };
+                    stream.write_all(response.as_bytes()).await.unwrap();
+                }
+                requests
+            });
+            let client = Client::builder("127.0.0.1", port)
+                .retries(1)
+                .sequence_commands(true)
+                .build()
+                .unwrap();
+
+            client
+                .set_switch_position(SwitchPosition::On)
+                .await
+                .unwrap();
+            client
+                .set_switch_position(SwitchPosition::Off)
+                .await
+                .unwrap();
+            let requests = server.await.unwrap();
+
+            let sequences: Vec<_> = requests.iter().map(|v| sequence_of(v)).collect();
+            assert_eq!(sequences[0], sequences[1]);
+            assert_eq!(sequences[2], sequences[3]);
+            assert_ne!(sequences[0], sequences[2]);
+            assert_eq!(
+                client.last_command().unwrap().body,
+                requests[3].split_once("\r\n\r\n").unwrap().1
+            );
+        }
+
+        #[tokio::test]
+        async fn not_sent_by_default() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/switches")
+                    .body(load_fixture("request_switches_ok.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.set_switch_position(SwitchPosition::On).await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[test]
+        fn never_reused() {
+            let last = AtomicU64::new(u64::MAX / 2);
+
+            let first = next_sequence(&last);
+            let second = next_sequence(&last);
+
+            assert_eq!(first, u64::MAX / 2 + 1);
+            assert_eq!(second, first + 1);
+        }
+    }
+
+    mod serialize_requests {
+        use super::*;
+
+        async fn completion_order(client: Client, server: &MockServer) -> Vec<&'static str> {
+            server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/switches")
+                    .body(load_fixture("request_switches_ok.json"));
+                then.status(200)
+                    .delay(Duration::from_millis(200))
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/startups");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+            let client = Arc::new(client);
+            let order = Arc::new(Mutex::new(vec![]));
+
+            let tasks: Vec<_> = (0..4)
+                .map(|i| {
+                    let client = client.clone();
+                    let order = order.clone();
+                    tokio::spawn(async move {
+                        let name = if i % 2 == 0 {
+                            client
+                                .set_switch_position(SwitchPosition::On)
+                                .await
+                                .unwrap();
+                            "switches"
+                        } else {
+                            client
+                                .set_startup_position(StartupPosition::Stay)
+                                .await
+                                .unwrap();
+                            "startups"
+                        };
+                        order.lock().unwrap().push(name);
+                    })
+                })
+                .collect();
+            for task in tasks {
+                task.await.unwrap();
+            }
+
+            let got = order.lock().unwrap().clone();
+            got
+        }
+
+        #[tokio::test]
+        async fn sent_in_order() {
+            let server = MockServer::start();
+            let client = Client::builder(server.host(), server.port())
+                .serialize_requests(true)
+                .build()
+                .unwrap();
+
+            let got = completion_order(client, &server).await;
+
+            assert_eq!(got, vec!["switches", "startups", "switches", "startups"]);
+        }
+
+        #[tokio::test]
+        async fn interleaved_by_default() {
+            let (server, client) = make_server_and_client();
+
+            let got = completion_order(client, &server).await;
+
+            assert_eq!(got, vec!["startups", "startups", "switches", "switches"]);
+        }
+    }
+
+    mod request_builders {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::info(Client::build_info_request(), "/zeroconf/info", "{\"data\":{}}")]
+        #[case::startup(
+            Client::build_startup_request(StartupPosition::Stay, outlet(0)),
+            "/zeroconf/startups",
+            &load_fixture("request_startups_ok.json")
+        )]
+        #[case::switch(
+            Client::build_switch_request(SwitchPosition::On, outlet(0)),
+            "/zeroconf/switches",
+            &load_fixture("request_switches_ok.json")
+        )]
+        #[case::switch_other_outlet(
+            Client::build_switch_request(SwitchPosition::Off, outlet(2)),
+            "/zeroconf/switches",
+            &load_fixture("request_switches_outlet_2.json")
+        )]
+        #[case::sled_brightness(
+            Client::build_sled_brightness_request(30).unwrap(),
+            "/zeroconf/sledBrightness",
+            &load_fixture("request_sled_brightness_ok.json")
+        )]
+        #[case::status_led(
+            Client::build_status_led_request(false),
+            "/zeroconf/sledonline",
+            &load_fixture("request_sledonline_off.json")
+        )]
+        #[case::pulse(
+            Client::build_pulse_request(
+                outlet(0),
+                PulsePosition::On,
+                Duration::from_millis(1500),
+                Some(PulseMode::DelayOn)
+            )
+            .unwrap(),
+            "/zeroconf/pulse",
+            &load_fixture("request_pulse_delay_on.json")
+        )]
+        #[case::cancel_pulse(
+            Client::build_cancel_pulse_request(OutletIndex::new(0).unwrap()),
+            "/zeroconf/pulse",
+            &load_fixture("request_pulse_cancel.json")
+        )]
+        #[cfg_attr(
+            feature = "ota",
+            case::ota_unlock(
+                Client::build_ota_unlock_request("1000abcdef"),
+                "/zeroconf/ota_unlock",
+                &load_fixture("request_ota_unlock_ok.json")
+            )
+        )]
+        #[cfg_attr(
+            feature = "ota",
+            case::ota_flash(
+                Client::build_ota_flash_request(FIRMWARE_URL, FIRMWARE_SHA256).unwrap(),
+                "/zeroconf/ota_flash",
+                &load_fixture("request_ota_flash_ok.json")
+            )
+        )]
+        #[cfg_attr(
+            feature = "wifi",
+            case::wifi(
+                Client::build_wifi_request("home", "secret"),
+                "/zeroconf/wifi",
+                &load_fixture("request_wifi_ok.json")
+            )
+        )]
+        fn returns_expected_result(
+            #[case] got: (String, Vec<u8>),
+            #[case] endpoint: &str,
+            #[case] body: &str,
+        ) {
+            assert_eq!(got.0, endpoint);
+            assert_eq!(String::from_utf8(got.1).unwrap(), body);
+        }
+
+        #[test]
+        fn errored_on_invalid_sled_brightness() {
+            let got = Client::build_sled_brightness_request(101);
+
+            assert!(matches!(got, Err(Error::InvalidParameter(_))));
+        }
+
+        #[test]
+        fn errored_on_invalid_pulse_width() {
+            let got = Client::build_pulse_request(
+                outlet(0),
+                PulsePosition::On,
+                Duration::from_millis(100),
+                None,
+            );
+
+            assert!(matches!(got, Err(Error::InvalidParameter(_))));
+        }
+    }
+
+    mod with_budget {
+        use super::*;
+        use std::time::Instant;
+
+        fn make_slow_server_and_client() -> (MockServer, Client) {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST");
+                then.status(200)
+                    .delay(Duration::from_millis(300))
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+            (server, client)
+        }
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let (_server, client) = make_slow_server_and_client();
+
+            let got = with_budget(Duration::from_secs(2), async {
+                client.set_startup_position(StartupPosition::Stay).await?;
+                client.set_switch_position(SwitchPosition::On).await
+            })
+            .await;
+
+            assert!(got.is_ok());
+        }
+
+        #[tokio::test]
+        async fn errored_if_exceeded() {
+            let (_server, client) = make_slow_server_and_client();
+            let started = Instant::now();
+
+            let got = with_budget(Duration::from_millis(400), async {
+                client.set_startup_position(StartupPosition::Stay).await?;
+                client.set_switch_position(SwitchPosition::On).await
+            })
+            .await;
+
+            assert_eq!(
+                got.unwrap_err(),
+                Error::BudgetExceeded(Duration::from_millis(400))
+            );
+            assert!(started.elapsed() < Duration::from_millis(600));
+        }
+    }
+
+    mod resolved_addr {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::ipv4("192.168.1.10", "192.168.1.10:8081")]
+        #[case::ipv6("[fe80::1]", "[fe80::1]:8081")]
+        #[tokio::test]
+        async fn returns_ip_literal_without_lookup(#[case] host: &str, #[case] expected: &str) {
+            let got = resolve_addr(host, 8081, |_| async { unreachable!() }).await;
+
+            assert_eq!(got.unwrap(), expected.parse().unwrap())
+        }
+
+        #[tokio::test]
+        async fn returns_first_resolved_address() {
+            let got = resolve_addr("sonoff.local", 8081, |v| async move {
+                assert_eq!(v, "sonoff.local:8081");
+                Ok(vec![
+                    "192.168.1.10:8081".parse().unwrap(),
+                    "192.168.1.11:8081".parse().unwrap(),
+                ])
+            })
+            .await;
+
+            assert_eq!(got.unwrap(), "192.168.1.10:8081".parse().unwrap())
+        }
+
+        #[tokio::test]
+        async fn errored_if_nothing_resolved() {
+            let got = resolve_addr("sonoff.local", 8081, |_| async { Ok(vec![]) }).await;
+
+            assert_eq!(
+                got.unwrap_err().to_string(),
+                "sonoff.local resolved to no addresses"
+            )
+        }
+
+        #[tokio::test]
+        async fn returns_address_of_client_host() {
+            let client = Client::new("127.0.0.1", 8081);
+
+            let got = client.resolved_addr().await;
+
+            assert_eq!(got.unwrap(), "127.0.0.1:8081".parse().unwrap())
+        }
+    }
+
+    mod set_timeout {
+        use super::*;
+
+        #[tokio::test]
+        async fn applied_to_requests() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/switches");
+                then.status(200)
+                    .delay(Duration::from_millis(500))
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            client.set_timeout(Some(Duration::from_millis(50)));
+            let got = client.set_switch_position(SwitchPosition::On).await;
+
+            assert_eq!(got.unwrap_err(), Error::TimedOut(Duration::from_millis(50)));
+        }
+
+        #[tokio::test]
+        async fn changed_while_shared() {
+            let client = Arc::new(Client::new("127.0.0.1", 8081));
+
+            let readers: Vec<_> = (0..8)
+                .map(|_| {
+                    let client = client.clone();
+                    tokio::spawn(async move {
+                        for _ in 0..1000 {
+                            if let Some(v) = client.timeout() {
+                                assert!(v >= Duration::from_millis(1));
+                            }
+                            tokio::task::yield_now().await;
+                        }
+                    })
+                })
+                .collect();
+            let writer = {
+                let client = client.clone();
+                tokio::spawn(async move {
+                    for i in 1..=1000 {
+                        client.set_timeout(Some(Duration::from_millis(i)));
+                        tokio::task::yield_now().await;
+                    }
+                })
+            };
+            for task in readers {
+                task.await.unwrap();
+            }
+            writer.await.unwrap();
+
+            assert_eq!(client.timeout(), Some(Duration::from_millis(1000)));
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    mod reap_idle_connections {
+        use super::*;
+
+        // Reaper task holds the pool, so it's released once task is stopped
+        async fn pool_holders(client: &Client) -> usize {
+            tokio::task::yield_now().await;
+            Arc::strong_count(&client.inner)
+        }
+
+        #[tokio::test]
+        async fn stopped_on_close() {
+            let client = Client::builder("127.0.0.1", 8081)
+                .reap_idle_connections(Duration::from_secs(60))
+                .build()
+                .unwrap();
+            assert_eq!(pool_holders(&client).await, 2);
+
+            client.close().await;
+
+            assert_eq!(pool_holders(&client).await, 1);
+        }
+
+        #[tokio::test]
+        async fn stopped_on_close_of_clone() {
+            let client = Client::builder("127.0.0.1", 8081)
+                .reap_idle_connections(Duration::from_secs(60))
+                .build()
+                .unwrap();
+            assert_eq!(pool_holders(&client).await, 2);
+
+            client.clone().close().await;
+
+            assert_eq!(pool_holders(&client).await, 1);
+        }
+
+        #[tokio::test]
+        async fn stopped_with_client() {
+            let client = Client::builder("127.0.0.1", 8081)
+                .reap_idle_connections(Duration::from_secs(60))
+                .build()
+                .unwrap();
+            let pool = client.inner.clone();
+            tokio::task::yield_now().await;
+            assert_eq!(Arc::strong_count(&pool), 3);
+
+            drop(client);
+            tokio::task::yield_now().await;
+
+            assert_eq!(Arc::strong_count(&pool), 1);
+        }
+
+        #[test]
+        fn errored_outside_of_runtime() {
+            let got = Client::builder("127.0.0.1", 8081)
+                .reap_idle_connections(Duration::from_secs(60))
+                .build();
+
+            assert!(got.is_err());
+        }
+    }
+
+    mod build {
+        use super::*;
+        use rstest::rstest;
+
+        #[test]
+        fn errored_on_empty_default_outlets() {
+            let got = Client::builder("127.0.0.1", 8081)
+                .default_outlets(&[])
+                .build();
+
+            assert_eq!(
+                got.err().unwrap(),
+                Error::InvalidParameter("default outlets must not be empty".to_string())
+            );
+        }
+
+        #[test]
+        fn errored_on_zero_port() {
+            let got = Client::builder("127.0.0.1", 0).build();
+
+            assert_eq!(
+                got.err().unwrap(),
+                Error::InvalidParameter("port must not be 0".to_string())
+            );
+        }
+
+        #[test]
+        fn overridden_host_and_port() {
+            let got = Client::builder("127.0.0.1", 0)
+                .host("192.168.1.75")
+                .port(8081)
+                .build()
+                .unwrap();
+
+            assert_eq!(got.url("info"), "http://192.168.1.75:8081/zeroconf/info");
+        }
+
+        #[rstest]
+        #[case::ipv4("192.168.1.75", "http://192.168.1.75:8081/zeroconf/info")]
+        #[case::hostname("sonoff.local", "http://sonoff.local:8081/zeroconf/info")]
+        #[case::ipv6("fe80::1", "http://[fe80::1]:8081/zeroconf/info")]
+        #[case::bracketed_ipv6("[fe80::1]", "http://[fe80::1]:8081/zeroconf/info")]
+        fn formatted_url(#[case] host: &str, #[case] expected: &str) {
+            let got = Client::builder(host, 8081).build().unwrap();
+
+            assert_eq!(got.url("info"), expected);
+        }
+
+        #[test]
+        fn applied_scheme() {
+            let got = Client::builder("sonoff.example.com", 443)
+                .scheme(Scheme::Https)
+                .build()
+                .unwrap();
+
+            assert_eq!(
+                got.url("info"),
+                "https://sonoff.example.com:443/zeroconf/info"
+            );
+            assert_eq!(got.effective_config()["scheme"], "https");
+            assert_eq!(got.effective_config()["encrypted"], true);
+        }
+
+        #[test]
+        fn errored_on_scoped_ipv6() {
+            let got = Client::builder("fe80::1%eth0", 8081).build();
+
+            assert_eq!(
+                got.err().unwrap(),
+                Error::InvalidParameter(
+                    "scoped IPv6 address fe80::1%eth0 is unsupported".to_string()
+                )
+            );
+        }
+
+        #[test]
+        fn errored_on_zero_port_override() {
+            let got = Client::builder("127.0.0.1", 8081).port(0).build();
+
+            assert!(got.is_err());
+        }
+
+        #[tokio::test]
+        async fn applied_timeout() {
+            let server = MockServer::start();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/switches");
+                then.status(200)
+                    .delay(Duration::from_millis(500))
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+            let client = Client::builder(server.host(), server.port())
+                .timeout(Duration::from_millis(50))
+                .build()
+                .unwrap();
+
+            let got = client.set_switch_position(SwitchPosition::On).await;
+
+            assert_eq!(client.timeout(), Some(Duration::from_millis(50)));
+            assert_eq!(got.unwrap_err(), Error::TimedOut(Duration::from_millis(50)));
+        }
+
+        #[test]
+        #[should_panic(expected = "port must not be 0")]
+        fn new_panics_on_zero_port() {
+            Client::new("127.0.0.1", 0);
+        }
+    }
+
+    mod record_to {
+        use super::*;
+
+        #[tokio::test]
+        async fn recorded_every_command() {
+            let server = MockServer::start();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_ok.json"));
+            });
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/switches");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+            let path = std::env::temp_dir().join(format!("sonoff-record-{}.jsonl", server.port()));
+            let _ = std::fs::remove_file(&path);
+            let client = Client::builder(server.host(), server.port())
+                .record_to(&path)
+                .build()
+                .unwrap();
+
+            client.fetch_info().await.unwrap();
+            client
+                .set_switch_position(SwitchPosition::On)
+                .await
+                .unwrap();
+
+            let recorded = std::fs::read_to_string(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            let lines: Vec<serde_json::Value> = recorded
+                .lines()
+                .map(|v| serde_json::from_str(v).unwrap())
+                .collect();
+            assert_eq!(lines.len(), 2);
+            assert_eq!(lines[0]["endpoint"], "/zeroconf/info");
+            assert_eq!(lines[0]["request"], "{\"data\":{}}");
+            assert_eq!(lines[0]["response"], load_fixture("response_info_ok.json"));
+            assert_eq!(lines[1]["endpoint"], "/zeroconf/switches");
+            assert_eq!(
+                lines[1]["request"],
+                load_fixture("request_switches_ok.json")
+            );
+            assert_eq!(lines[1]["response"], load_fixture("response_ok.json"));
+            assert!(lines[1]["timestamp"].as_u64().unwrap() > 0);
+        }
+    }
+
+    mod with_timeout {
+        use super::*;
+
+        #[tokio::test]
+        async fn errored_in_expected_way() {
+            let server = MockServer::start();
+            let mock = server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/switches");
+                then.status(200)
+                    .delay(Duration::from_millis(500))
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+            let client =
+                Client::with_timeout(server.host(), server.port(), Duration::from_millis(50));
+
+            let got = client
+                .set_switch_position(SwitchPosition::On)
+                .await
+                .unwrap_err();
+
+            mock.assert();
+
+            assert_eq!(got, Error::TimedOut(Duration::from_millis(50)));
+        }
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let server = MockServer::start();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/switches");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+            let client = Client::with_timeout(server.host(), server.port(), Duration::from_secs(5));
+
+            let got = client.set_switch_position(SwitchPosition::On).await;
+
+            assert!(got.is_ok());
+        }
+    }
+
+    mod connect_timeout {
+        use super::*;
+        use rstest::rstest;
+
+        // Listener with backlog that is already full, so further connections hang
+        async fn unresponsive_listener() -> (tokio::net::TcpListener, std::net::TcpStream) {
+            let socket = tokio::net::TcpSocket::new_v4().unwrap();
+            socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+            let listener = socket.listen(0).unwrap();
+            let filler = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+            (listener, filler)
+        }
+
+        #[rstest]
+        #[case::with_request_timeout(Some(Duration::from_secs(5)))]
+        #[case::without_request_timeout(None)]
+        #[tokio::test]
+        async fn errored_with_connect_timeout(#[case] timeout: Option<Duration>) {
+            let (listener, _filler) = unresponsive_listener().await;
+            let addr = listener.local_addr().unwrap();
+            let mut builder = Client::builder(addr.ip().to_string(), addr.port())
+                .connect_timeout(Duration::from_millis(50));
+            if let Some(v) = timeout {
+                builder = builder.timeout(v);
+            }
+            let client = builder.build().unwrap();
+
+            let got = client.fetch_info().await;
+
+            assert_eq!(got.unwrap_err(), Error::TimedOut(Duration::from_millis(50)));
+        }
+    }
+
+    mod with_http_client {
+        use super::*;
+
+        #[tokio::test]
+        async fn sent_with_given_client() {
+            let server = MockServer::start();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/switches")
+                    .header("user-agent", "custom/1.0");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+            let http = reqwest::Client::builder()
+                .user_agent("custom/1.0")
+                .build()
+                .unwrap();
+            let client = Client::with_http_client(server.host(), server.port(), http);
+
+            let got = client.set_switch_position(SwitchPosition::On).await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+    }
+
+    mod with_defaults_for_diy_ap {
+        use super::*;
+
+        #[test]
+        fn returns_expected_client() {
+            let client = Client::with_defaults_for_diy_ap();
+
+            assert_eq!(client.url("info"), "http://10.10.7.1:8081/zeroconf/info");
+            assert_eq!(client.connect_timeout(), Some(Duration::from_secs(2)));
+            assert_eq!(client.retries, 0);
+        }
+    }
+
+    mod last_command {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_most_recent_command() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+            let before = SystemTime::now();
+
+            assert!(client.last_command().is_none());
+
+            client
+                .set_startup_position(StartupPosition::Stay)
+                .await
+                .unwrap();
+            client
+                .set_switch_position(SwitchPosition::On)
+                .await
+                .unwrap();
+            let got = client.last_command().unwrap();
+
+            assert_eq!(got.endpoint, "/zeroconf/switches");
+            assert_eq!(got.body, load_fixture("request_switches_ok.json"));
+            assert!(got.timestamp >= before);
+        }
+    }
+
+    mod compare_and_set_switch {
+        use super::*;
+
+        fn mock_switches(server: &MockServer) -> httpmock::Mock<'_> {
+            server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/switches")
+                    .body(load_fixture("request_switches_ok.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            })
         }
-    }
 
-    fn url(&self, path: &str) -> String {
-        format!(
-            "http://{host}:{port}/zeroconf/{path}",
-            host = self.host,
-            port = self.port
-        )
-    }
+        #[tokio::test]
+        async fn set_if_matches() {
+            let (server, client) = mock_client_returning("info", "response_info_ok.json");
+            let mock = mock_switches(&server);
 
-    /// Fetch device info.
-    ///
-    /// In current implementation it always uses `/zeroconf/info` API and returns limited info. For
-    /// more details take a look at `Info` struct.
-    ///
-    /// # Example
-    ///
-    /// ```ignore
-    /// let got = client.fetch_info().await;
-    ///
-    /// assert!(got.is_ok());
-    /// assert_eq!(
-    ///     got.unwrap(),
-    ///     Info {
-    ///         switch: SwitchPosition::Off,
-    ///         startup: StartupPosition::Off
-    ///     }
-    /// )
-    /// ```
-    pub async fn fetch_info(&self) -> anyhow::Result<Info> {
-        Ok(self
-            .inner
-            .post(self.url("info"))
-            .body("{\"data\":{}}")
-            .send()
-            .await?
-            .json::<InfoResponse>()
-            .await?
-            .try_into()?)
-    }
+            let got = client
+                .compare_and_set_switch(SwitchPosition::Off, SwitchPosition::On)
+                .await;
 
-    /// Set startup position for device.
-    ///
-    /// It uses `/zeroconf/startups` API and always sets given position only for outlet 0.
-    /// Other outlets will be set to `off` on every call, because API doesn't allow to specify
-    /// state for one outlet only.
-    ///
-    /// # Example
-    ///
-    /// ```ignore
-    /// let got = client.set_startup_position(StartupPosition::Stay).await;
-    ///
-    /// assert!(got.is_ok());
-    /// ```
-    pub async fn set_startup_position(&self, position: StartupPosition) -> anyhow::Result<()> {
-        Ok(self
-            .inner
-            .post(self.url("startups"))
-            .json(&StartupsRequest::from(position))
-            .send()
-            .await?
-            .json::<EmptyResponse>()
-            .await?
-            .try_into()?)
-    }
+            mock.assert();
 
-    /// Set switch position.
-    ///
-    /// Is uses `/zeroconf/switches` API and always sets given position for outlet 0 only. This API
-    /// allows to ignore state of another outlets, so they will be ignored.
-    ///
-    /// # Example
-    /// ```ignore
-    /// let got = client.set_switch_position(SwitchPosition::On).await;
-    ///
-    /// assert!(got.is_err());
-    /// assert_eq!(
-    ///     got.unwrap_err().downcast::<Error>().unwrap(),
-    ///     Error::WrongParameters
-    /// )
-    /// ```
-    pub async fn set_switch_position(&self, position: SwitchPosition) -> anyhow::Result<()> {
-        Ok(self
-            .inner
-            .post(self.url("switches"))
-            .json(&SwitchesRequest::from(position))
-            .send()
-            .await?
-            .json::<EmptyResponse>()
-            .await?
-            .try_into()?)
-    }
-}
+            assert!(got.unwrap());
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use httpmock::MockServer;
+        #[tokio::test]
+        async fn skipped_if_mismatches() {
+            let (server, client) = mock_client_returning("info", "response_info_ok.json");
+            let mock = mock_switches(&server);
 
-    fn load_fixture(fpath: &str) -> String {
-        let read = std::fs::read_to_string(format!("./testing_fixtures/{}", fpath)).unwrap();
-        jsonxf::minimize(&read).unwrap()
-    }
+            let got = client
+                .compare_and_set_switch(SwitchPosition::On, SwitchPosition::On)
+                .await;
 
-    fn make_server_and_client() -> (MockServer, Client) {
-        let server = MockServer::start();
-        let client = Client::new(server.host(), server.port());
-        (server, client)
+            mock.assert_hits(0);
+
+            assert!(!got.unwrap());
+        }
+
+        #[tokio::test]
+        async fn errored_on_missing_outlet() {
+            let (server, client) =
+                mock_client_returning("info", "response_info_missing_outlet.json");
+            let mock = mock_switches(&server);
+
+            let got = client
+                .compare_and_set_switch(SwitchPosition::Off, SwitchPosition::On)
+                .await;
+
+            mock.assert_hits(0);
+
+            assert_eq!(
+                got.unwrap_err(),
+                Error::MalformedField {
+                    name: "switches".to_string(),
+                    message: "outlet 0 is missing".to_string(),
+                }
+            )
+        }
     }
 
-    mod info {
+    mod default_outlet {
         use super::*;
 
+        fn make_server_and_client() -> (MockServer, Client) {
+            let server = MockServer::start();
+            let client = Client::builder(server.host(), server.port())
+                .default_outlet(2)
+                .build()
+                .unwrap();
+            (server, client)
+        }
+
         #[tokio::test]
-        async fn returns_expected_result() {
+        async fn read_by_fetch_info() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_mixed_outlets.json"));
+            });
+
+            let got = client.fetch_info().await.unwrap();
+
+            assert_eq!(got.switch, SwitchPosition::On);
+            assert_eq!(got.startup, StartupPosition::On);
+        }
+
+        #[tokio::test]
+        async fn read_by_fetch_info_lenient() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_mixed_outlets.json"));
+            });
+
+            let got = client.fetch_info_lenient().await.unwrap();
+
+            assert_eq!(got.switch, Ok(SwitchPosition::On));
+            assert_eq!(got.startup, Ok(StartupPosition::On));
+        }
+
+        #[tokio::test]
+        async fn read_by_fetch_pulse_width() {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_pulse_on.json"));
+            });
+
+            let got = client.fetch_pulse_width().await;
+
+            assert_eq!(got.unwrap(), Some(Duration::ZERO));
+        }
+
+        #[tokio::test]
+        async fn set_by_set_switch_position() {
             let (server, client) = make_server_and_client();
             let mock = server.mock(|when, then| {
                 when.method("POST")
-                    .path("/zeroconf/info")
-                    .body("{\"data\":{}}");
+                    .path("/zeroconf/switches")
+                    .body(load_fixture("request_switches_outlet_2.json"));
                 then.status(200)
                     .header("content-type", "application/json; charset=utf-8")
-                    .body(load_fixture("response_info_ok.json"));
+                    .body(load_fixture("response_ok.json"));
             });
 
-            let got = client.fetch_info().await;
+            let got = client.set_switch_position(SwitchPosition::Off).await;
 
             mock.assert();
 
             assert!(got.is_ok());
-            assert_eq!(
-                got.unwrap(),
-                Info {
-                    switch: SwitchPosition::Off,
-                    startup: StartupPosition::Off
-                }
-            )
         }
 
         #[tokio::test]
-        async fn errored_in_expected_way() {
+        async fn cancelled_by_cancel_pulse() {
             let (server, client) = make_server_and_client();
             let mock = server.mock(|when, then| {
                 when.method("POST")
-                    .path("/zeroconf/info")
-                    .body("{\"data\":{}}");
-                then.status(400)
+                    .path("/zeroconf/pulse")
+                    .json_body(serde_json::json!({"data": {"pulse": "off", "outlet": 2}}));
+                then.status(200)
                     .header("content-type", "application/json; charset=utf-8")
-                    .body(load_fixture("response_error.json"));
+                    .body(load_fixture("response_ok.json"));
             });
 
-            let got = client.fetch_info().await;
+            let got = client.cancel_pulse().await;
 
             mock.assert();
 
-            assert!(got.is_err());
+            assert!(got.is_ok());
+        }
+
+        #[tokio::test]
+        async fn set_by_set_startup_position() {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/startups").json_body(
+                    serde_json::json!({"data": {"configure": [
+                        {"startup": "stay", "outlet": 2},
+                    ]}}),
+                );
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.set_startup_position(StartupPosition::Stay).await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[test]
+        fn errored_on_out_of_range_outlet() {
+            let got = Client::builder("127.0.0.1", 8081).default_outlet(4).build();
+
             assert_eq!(
-                got.unwrap_err().downcast::<Error>().unwrap(),
-                Error::WrongParameters
-            )
+                got.err().unwrap(),
+                Error::InvalidParameter("outlet should be within 0..=3, got 4".to_string())
+            );
         }
     }
 
-    mod set_startup_position {
+    mod toggle_switch {
         use super::*;
+        use rstest::rstest;
 
+        #[rstest]
+        #[case::off_to_on(
+            "response_info_ok.json",
+            r#"{ "data": { "switches": [{ "switch": "on", "outlet": 0 }] } }"#,
+            SwitchPosition::On
+        )]
+        #[case::on_to_off(
+            "response_info_mixed_outlets.json",
+            r#"{ "data": { "switches": [{ "switch": "off", "outlet": 0 }] } }"#,
+            SwitchPosition::Off
+        )]
         #[tokio::test]
-        async fn sent_expected_request() {
+        async fn returns_expected_result(
+            #[case] info: &str,
+            #[case] request: &str,
+            #[case] expected: SwitchPosition,
+        ) {
+            let (server, client) = mock_client_returning("info", info);
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/switches")
+                    .json_body(serde_json::from_str::<serde_json::Value>(request).unwrap());
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.toggle_switch().await;
+
+            mock.assert();
+
+            assert_eq!(got.unwrap(), expected);
+        }
+
+        #[tokio::test]
+        async fn errored_on_unknown_position() {
+            let (server, client) = make_server_and_lenient_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_unknown_switch.json"));
+            });
+            let mock = server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/switches");
+                then.status(200);
+            });
+
+            let got = client.toggle_switch().await;
+
+            mock.assert_hits(0);
+
+            assert_eq!(
+                got.unwrap_err(),
+                Error::UnexpectedSwitchPosition("unavailable".to_string())
+            );
+        }
+    }
+
+    mod set_switch_position {
+        use super::*;
+        use rstest::rstest;
+
+        #[tokio::test]
+        async fn targeted_default_outlets() {
+            let server = MockServer::start();
+            let client = Client::builder(server.host(), server.port())
+                .default_outlets(&[outlet(1), outlet(3)])
+                .build()
+                .unwrap();
+            let mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/switches")
+                    .body(load_fixture("request_switches_outlets_1_3.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.turn_on().await;
+
+            mock.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[rstest]
+        #[case::on(true, SwitchPosition::On)]
+        #[case::off(false, SwitchPosition::Off)]
+        #[tokio::test]
+        async fn sent_same_request_for_bool(#[case] value: bool, #[case] position: SwitchPosition) {
+            let (server, client) = make_server_and_client();
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/switches");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            client.set_switch_position(value).await.unwrap();
+            let from_bool = client.last_command().unwrap().body;
+            client.set_switch_position(position).await.unwrap();
+            let from_enum = client.last_command().unwrap().body;
+
+            assert_eq!(from_bool, from_enum);
+        }
+
+        #[tokio::test]
+        async fn returns_expected_result() {
             let (server, client) = make_server_and_client();
             let mock = server.mock(|when, then| {
                 when.method("POST")
-                    .path("/zeroconf/startups")
-                    .body(load_fixture("request_startups_ok.json"));
+                    .path("/zeroconf/switches")
+                    .body(load_fixture("request_switches_ok.json"));
                 then.status(200)
                     .header("content-type", "application/json; charset=utf-8")
                     .body(load_fixture("response_ok.json"));
             });
 
-            let got = client.set_startup_position(StartupPosition::Stay).await;
+            let got = client.set_switch_position(SwitchPosition::On).await;
 
             mock.assert();
 
@@ -215,31 +5388,53 @@ mod tests {
         }
 
         #[tokio::test]
-        async fn errored_in_expected_way() {
+        async fn fell_back_to_singular_endpoint() {
+            let (server, client) = make_server_and_client();
+            let switches_mock = server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/switches");
+                then.status(404);
+            });
+            let switch_mock = server.mock(|when, then| {
+                when.method("POST")
+                    .path("/zeroconf/switch")
+                    .body(load_fixture("request_switch_ok.json"));
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_ok.json"));
+            });
+
+            let got = client.set_switch_position(SwitchPosition::On).await;
+
+            switches_mock.assert();
+            switch_mock.assert();
+
+            assert!(got.is_ok());
+        }
+
+        #[tokio::test]
+        async fn sent_expected_request() {
             let (server, client) = make_server_and_client();
             let mock = server.mock(|when, then| {
                 when.method("POST")
-                    .path("/zeroconf/startups")
-                    .body(load_fixture("request_startups_ok.json"));
+                    .path("/zeroconf/switches")
+                    .body(load_fixture("request_switches_ok.json"));
                 then.status(400)
                     .header("content-type", "application/json; charset=utf-8")
                     .body(load_fixture("response_error.json"));
             });
 
-            let got = client.set_startup_position(StartupPosition::Stay).await;
+            let got = client.set_switch_position(SwitchPosition::On).await;
 
             mock.assert();
 
             assert!(got.is_err());
-            assert_eq!(
-                got.unwrap_err().downcast::<Error>().unwrap(),
-                Error::WrongParameters
-            )
+            assert_eq!(got.unwrap_err(), Error::WrongParameters(None))
         }
     }
 
-    mod set_switch_position {
+    mod set_switch_positions {
         use super::*;
+        use rstest::rstest;
 
         #[tokio::test]
         async fn sent_expected_request() {
@@ -247,20 +5442,52 @@ mod tests {
             let mock = server.mock(|when, then| {
                 when.method("POST")
                     .path("/zeroconf/switches")
-                    .body(load_fixture("request_switches_ok.json"));
-                then.status(400)
+                    .body(load_fixture("request_switches_mixed.json"));
+                then.status(200)
                     .header("content-type", "application/json; charset=utf-8")
-                    .body(load_fixture("response_error.json"));
+                    .body(load_fixture("response_ok.json"));
             });
 
-            let got = client.set_switch_position(SwitchPosition::On).await;
+            let got = client
+                .set_switch_positions(&[
+                    (outlet(0), SwitchPosition::On),
+                    (outlet(2), SwitchPosition::Off),
+                ])
+                .await;
 
             mock.assert();
 
-            assert!(got.is_err());
+            assert!(got.is_ok());
+        }
+
+        #[rstest]
+        #[case::empty(&[], "positions must not be empty")]
+        #[case::duplicate(
+            &[
+                (outlet(1), SwitchPosition::On),
+                (outlet(2), SwitchPosition::Off),
+                (outlet(1), SwitchPosition::Off),
+            ],
+            "duplicate outlet 1"
+        )]
+        #[tokio::test]
+        async fn errored_in_expected_way(
+            #[case] positions: &[(OutletIndex, SwitchPosition)],
+            #[case] expected: &str,
+        ) {
+            let (server, client) = make_server_and_client();
+            let mock = server.mock(|when, then| {
+                when.any_request();
+                then.status(200);
+            });
+
+            let got = client.set_switch_positions(positions).await;
+
+            mock.assert_hits(0);
+
             assert_eq!(
-                got.unwrap_err().downcast::<Error>().unwrap(),
-                Error::WrongParameters
+                got.unwrap_err(),
+                Error::InvalidParameter(expected.to_string())
             )
         }
     }
@@ -0,0 +1,237 @@
+use crate::{Client, Result};
+use mdns_sd::{DaemonEvent, ServiceDaemon, ServiceEvent};
+use std::io;
+use std::time::{Duration, Instant};
+
+/// mDNS service type advertised by devices in DIY mode.
+pub const SERVICE_TYPE: &str = "_ewelink._tcp.local.";
+
+/// Represents device found on the LAN.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveredDevice {
+    pub host: String,
+    pub port: u16,
+    pub device_id: String,
+    pub device_type: String,
+}
+
+/// Represents error encountered during discovery.
+///
+/// `interface` is set only by custom `Browser` implementations that could attribute error to
+/// specific network interface. `MdnsBrowser` always leaves it `None`, since `mdns-sd` daemon
+/// doesn't report which interface failed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveryError {
+    pub interface: Option<String>,
+    pub message: String,
+}
+
+/// Represents discovery outcome: devices that were found and errors that were encountered.
+///
+/// Non-empty `errors` doesn't mean that discovery failed, devices found despite them are still
+/// reported.
+#[derive(Debug, Default, PartialEq)]
+pub struct DiscoveryReport {
+    pub devices: Vec<DiscoveredDevice>,
+    pub errors: Vec<DiscoveryError>,
+}
+
+/// Represents single event reported by `Browser`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BrowseEvent {
+    Resolved(DiscoveredDevice),
+    Error(DiscoveryError),
+}
+
+/// Browses network for given service type.
+///
+/// Implemented by `MdnsBrowser`, which is used by default; custom implementations are useful for
+/// testing.
+pub trait Browser: Send + 'static {
+    /// Browse for `timeout` and return all events encountered.
+    fn browse(&self, service_type: &str, timeout: Duration) -> Result<Vec<BrowseEvent>>;
+}
+
+/// `Browser` implementation based on `mdns-sd` daemon.
+///
+/// Errors reported by daemon aren't attributed to network interfaces, failures of specific
+/// interfaces are only logged by daemon itself.
+pub struct MdnsBrowser;
+
+impl Browser for MdnsBrowser {
+    fn browse(&self, service_type: &str, timeout: Duration) -> Result<Vec<BrowseEvent>> {
+        // Daemon fails only if it couldn't set up its sockets or thread
+        let daemon = ServiceDaemon::new().map_err(io::Error::other)?;
+        let monitor = daemon.monitor().map_err(io::Error::other)?;
+        let receiver = daemon.browse(service_type).map_err(io::Error::other)?;
+
+        let mut events = vec![];
+        let deadline = Instant::now() + timeout;
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            match receiver.recv_timeout(remaining) {
+                Ok(ServiceEvent::ServiceResolved(service)) => {
+                    let device_id = service.get_property_val_str("id");
+                    let device_type = service.get_property_val_str("type");
+                    if let (Some(device_id), Some(device_type)) = (device_id, device_type) {
+                        let host = match service.get_addresses_v4().into_iter().next() {
+                            Some(v) => v.to_string(),
+                            None => service.get_hostname().trim_end_matches('.').to_string(),
+                        };
+                        events.push(BrowseEvent::Resolved(DiscoveredDevice {
+                            host,
+                            port: service.get_port(),
+                            device_id: device_id.to_string(),
+                            device_type: device_type.to_string(),
+                        }));
+                    }
+                }
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+
+        for event in monitor.try_iter() {
+            if let DaemonEvent::Error(e) = event {
+                events.push(BrowseEvent::Error(DiscoveryError {
+                    interface: None,
+                    message: e.to_string(),
+                }));
+            }
+        }
+
+        let _ = daemon.shutdown();
+        Ok(events)
+    }
+}
+
+/// Discover devices on the LAN via mDNS and report errors encountered along the way.
+///
+/// # Example
+///
+/// ```ignore
+/// let report = discover_detailed(Duration::from_secs(3)).await?;
+///
+/// for error in report.errors {
+///     eprintln!("{:?}: {}", error.interface, error.message);
+/// }
+/// ```
+pub async fn discover_detailed(timeout: Duration) -> Result<DiscoveryReport> {
+    discover_detailed_with(MdnsBrowser, timeout).await
+}
+
+/// Same as `discover_detailed`, but uses given `Browser`.
+pub async fn discover_detailed_with<B: Browser>(
+    browser: B,
+    timeout: Duration,
+) -> Result<DiscoveryReport> {
+    let events =
+        crate::runtime::spawn_blocking(move || browser.browse(SERVICE_TYPE, timeout)).await??;
+
+    let mut report = DiscoveryReport::default();
+    for event in events {
+        match event {
+            BrowseEvent::Resolved(device) => {
+                if !report.devices.contains(&device) {
+                    report.devices.push(device)
+                }
+            }
+            BrowseEvent::Error(error) => report.errors.push(error),
+        }
+    }
+    Ok(report)
+}
+
+impl Client {
+    /// Discover devices on the LAN via mDNS, browsing for `timeout`.
+    ///
+    /// Errors encountered during discovery are skipped, use `discover_detailed` to get them. Available only with `discovery` feature.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let devices = Client::discover(Duration::from_secs(3)).await?;
+    ///
+    /// let client = Client::new(devices[0].host.clone(), devices[0].port);
+    /// ```
+    pub async fn discover(timeout: Duration) -> Result<Vec<DiscoveredDevice>> {
+        Self::discover_with(MdnsBrowser, timeout).await
+    }
+
+    /// Same as `discover`, but uses given `Browser`.
+    pub async fn discover_with<B: Browser>(
+        browser: B,
+        timeout: Duration,
+    ) -> Result<Vec<DiscoveredDevice>> {
+        Ok(discover_detailed_with(browser, timeout).await?.devices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StubBrowser(Vec<BrowseEvent>);
+
+    impl Browser for StubBrowser {
+        fn browse(&self, service_type: &str, _: Duration) -> Result<Vec<BrowseEvent>> {
+            assert_eq!(service_type, SERVICE_TYPE);
+            Ok(self.0.clone())
+        }
+    }
+
+    fn device() -> DiscoveredDevice {
+        DiscoveredDevice {
+            host: "192.168.1.75".to_string(),
+            port: 8081,
+            device_id: "1000abcdef".to_string(),
+            device_type: "diy_plug".to_string(),
+        }
+    }
+
+    mod discover {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let browser = StubBrowser(vec![
+                BrowseEvent::Resolved(device()),
+                BrowseEvent::Error(DiscoveryError {
+                    interface: None,
+                    message: "Network is unreachable".to_string(),
+                }),
+                BrowseEvent::Resolved(device()),
+            ]);
+
+            let got = Client::discover_with(browser, Duration::from_millis(10)).await;
+
+            assert_eq!(got.unwrap(), vec![device()])
+        }
+    }
+
+    mod discover_detailed {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_devices_and_errors() {
+            let error = DiscoveryError {
+                interface: Some("eth1".to_string()),
+                message: "Network is unreachable".to_string(),
+            };
+            let browser = StubBrowser(vec![
+                BrowseEvent::Resolved(device()),
+                BrowseEvent::Error(error.clone()),
+                BrowseEvent::Resolved(device()),
+            ]);
+
+            let got = discover_detailed_with(browser, Duration::from_millis(10)).await;
+
+            assert_eq!(
+                got.unwrap(),
+                DiscoveryReport {
+                    devices: vec![device()],
+                    errors: vec![error],
+                }
+            )
+        }
+    }
+}
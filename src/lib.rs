@@ -7,14 +7,40 @@
 //! Also you may need to read [API documentation](https://sonoff.tech/diy-developer/) which is used
 //! to implement this lib.
 //!
-//! Currently library provides limited amount of features:
-//! - fetching device info (only few attributes)
-//! - setting startup position
-//! - setting current switch position
+//! Currently library provides following features:
+//! - fetching device info, either of default outlet, full or lenient one, along with faults,
+//!   capabilities, supported commands and WiFi signal strength
+//! - setting startup and current switch positions of one or several outlets, toggling switch
+//! - setting and cancelling pulse (inching)
+//! - setting status LED and its brightness
+//! - unlocking OTA, flashing and verifying firmware
+//! - setting WiFi credentials and DIY pairing helpers
+//! - discovering devices via mDNS and scanning IPv4 networks for them
+//! - caching device info in user supplied store across restarts
+//! - blocking client and Tower `Service` adapter
+//! - recording sent requests to a file
 //!
+//! Tokio is used as async runtime by default. To use async-std instead, disable default features
+//! and enable `async-std` one. Note that reqwest still needs tokio reactor to perform requests,
+//! so async-std is built with its `tokio1` compatibility, running the reactor in the background.
 //!
-//! Note that doscovery via mDNS is not supported, so you should know IP address of your device.
-//! Port is 8081 by default (just try it, should work).
+//! Larger features are gated behind cargo features, so minimal client for fetching info and
+//! setting switch and startup positions could be compiled with `default-features = false` and
+//! runtime feature only:
+//! - `ota` - unlocking OTA, flashing and verifying firmware, enabled by default
+//! - `wifi` - setting WiFi credentials and DIY pairing helpers, enabled by default
+//! - `blocking` - blocking client in `blocking` module, built on `reqwest::blocking`
+//! - `discovery` - mDNS discovery
+//! - `tower` - Tower `Service` adapter
+//! - `tracing` - `tracing` spans for client methods and events for sent requests, device responses
+//!   and API errors
+//!
+//! HTTPS is needed only for devices behind HTTPS reverse proxy, look at `Scheme` for details. TLS
+//! backend is chosen with `rustls-tls` (enabled by default) or `native-tls` feature, the latter
+//! links system TLS library, e.g. OpenSSL. Without both of them only plain HTTP is supported.
+//!
+//! Devices could be discovered via mDNS with `discovery` feature enabled, otherwise you should know
+//! IP address of your device. Port is 8081 by default (just try it, should work).
 //!
 //! Example:
 //! ```ignore
@@ -30,7 +56,18 @@
 //!     got.unwrap(),
 //!     Info {
 //!         switch: SwitchPosition::Off,
-//!         startup: StartupPosition::Off
+//!         startup: StartupPosition::Off,
+//!         status_led: true,
+//!         device_id: "1000abcdef".to_string(),
+//!         fw_version: "1.4.3".to_string(),
+//!         pulse: Some(Pulse {
+//!             pulse: PulsePosition::Off,
+//!             switch: SwitchPosition::Off,
+//!             outlet: 0,
+//!             width: PulseWidth(0),
+//!         }),
+//!         sled_brightness: None,
+//!         ssid: None
 //!     }
 //! )
 //!
@@ -42,8 +79,51 @@
 //! // Set current switch position
 //! client.set_switch_position(SwitchPosition::On).await;
 //! ```
+#[cfg(feature = "blocking")]
+pub mod blocking;
 mod client;
+#[cfg(feature = "discovery")]
+mod discovery;
 mod models;
+#[cfg(feature = "wifi")]
+mod provisioning;
+mod reaper;
+mod recorder;
+mod runtime;
+mod scan;
+#[cfg(feature = "tower")]
+mod service;
+mod store;
+#[cfg(feature = "tokio")]
+mod tasks;
+#[cfg(test)]
+mod test_support;
 
 pub use client::*;
+#[cfg(feature = "discovery")]
+pub use discovery::*;
 pub use models::*;
+#[cfg(feature = "wifi")]
+pub use provisioning::*;
+pub use scan::*;
+#[cfg(feature = "tower")]
+pub use service::*;
+pub use store::*;
+
+/// Result type returned by this crate, look at `Error` for possible errors.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Internals exposed for benchmarks only, not a part of public API.
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench {
+    use crate::models::*;
+
+    pub fn switches_body(position: SwitchPosition) -> usize {
+        SwitchesRequest::body(position).len()
+    }
+
+    pub fn switches_body_serialized(position: SwitchPosition) -> usize {
+        SwitchesRequest::serialize(position).len()
+    }
+}
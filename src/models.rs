@@ -1,48 +1,306 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
-const OUTLET2USE: u8 = 0;
+const PULSE_WIDTH_STEP_MS: u128 = 500;
+const MAX_PULSE_WIDTH_MS: u128 = 36_000_000;
+const BODY_SNIPPET_LEN: usize = 200;
 
-/// Represent errors that might be returned by device API.
+/// Outlet used by client unless configured otherwise.
+pub(crate) const DEFAULT_OUTLET: OutletIndex = OutletIndex(0);
+/// The only outlet of older single-channel firmware.
+pub(crate) const FIRST_OUTLET: OutletIndex = OutletIndex(0);
+
+/// Represent errors that might be returned by this crate.
 ///
-/// Currently only code 400 is supported, presented as `WrongParameters`
-#[derive(Debug, Clone, PartialEq)]
+/// Documented API error codes are presented as `WrongParameters` (400), `Unauthorized` (401),
+/// `UnsupportedEndpoint` (404) and `InvalidRequestParameters` (422), other codes are presented as
+/// `Unknown` with raw code. They hold human-readable message if device reported one.
+/// `UnexpectedSwitchPosition` is returned when device reports switch position unknown to this
+/// crate and client is not configured to be lenient.
+/// `InvalidDeviceId` is returned when given device id isn't 10 hex digits.
+/// `FirmwareMismatch` is returned when device runs firmware other than expected one.
+/// `InvalidParameter` is returned when given parameter is rejected before sending it to device.
+/// `BudgetExceeded` is returned when operation didn't fit into time budget given to `with_budget`.
+/// `TimedOut` is returned with configured timeout when device didn't respond in time.
+/// `MalformedField` is returned for field of `PartialInfo` device reported in unexpected form.
+/// `UnexpectedResponse` is returned when device reported success, but response lacks expected
+/// details, e.g. info without `data`.
+/// `OtaUnlockFailed` is returned with API error code when device failed to unlock OTA, e.g. it
+/// isn't connected to the cloud.
+/// `InvalidSha256` is returned when given firmware checksum isn't 64 hex digits.
+/// `OtaLocked`, `FirmwareDownloadTimedOut`, `FirmwareTooLarge`, `FirmwareDownloadFailed` and
+/// `FirmwareChecksumMismatch` are returned when device failed to flash firmware, look at
+/// `Client::ota_flash` for details.
+/// `Http` is returned when request couldn't be sent or response couldn't be read,
+/// `HttpStatus` when device or something in between responded with server error status,
+/// `MalformedResponse` when response body couldn't be parsed, e.g. it's a router login page, it
+/// holds parsing error along with beginning of the body. `Json` is returned when request body
+/// couldn't be serialized and `Io` for the rest IO failures, e.g. host lookup or recording
+/// requests. They wrap underlying error, which is shared between clones.
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum Error {
-    WrongParameters,
+    #[error("{}", api_message(400, .0, "wrong parameters"))]
+    WrongParameters(Option<String>),
+    #[error("{}", api_message(401, .0, "request is unauthorized"))]
+    Unauthorized(Option<String>),
+    #[error("{}", api_message(404, .0, "endpoint is unsupported"))]
+    UnsupportedEndpoint(Option<String>),
+    #[error("{}", api_message(422, .0, "invalid request parameters"))]
+    InvalidRequestParameters(Option<String>),
+    #[error("{}", match .1 {
+        Some(v) => format!("API errored with code {}: {}", .0, v),
+        None => format!("API errored with unknown code {}", .0),
+    })]
+    Unknown(usize, Option<String>),
+    #[error("Invalid parameter: {0}")]
+    InvalidParameter(String),
+    #[error("Device reported unexpected switch position: {0}")]
+    UnexpectedSwitchPosition(String),
+    #[error("Invalid device id: {0}")]
+    InvalidDeviceId(String),
+    #[error(
+        "Expected firmware {expected}, but device runs {}",
+        actual.as_deref().unwrap_or("unknown")
+    )]
+    FirmwareMismatch {
+        expected: String,
+        actual: Option<String>,
+    },
+    #[error("Operation exceeded time budget of {0:?}")]
+    BudgetExceeded(Duration),
+    #[error("Request timed out after {0:?}")]
+    TimedOut(Duration),
+    #[error("Device reported malformed {name}: {message}")]
+    MalformedField { name: String, message: String },
+    #[error("Device reported unexpected response: {0}")]
+    UnexpectedResponse(String),
+    #[error("OTA unlock failed with code {0}")]
+    OtaUnlockFailed(usize),
+    #[error("Invalid SHA-256 checksum: {0}")]
+    InvalidSha256(String),
+    #[error("OTA is locked, unlock it first")]
+    OtaLocked,
+    #[error("Firmware download timed out")]
+    FirmwareDownloadTimedOut,
+    #[error("Firmware is too large for device")]
+    FirmwareTooLarge,
+    #[error("Device failed to download firmware")]
+    FirmwareDownloadFailed,
+    #[error("Downloaded firmware doesn't match checksum")]
+    FirmwareChecksumMismatch,
+    #[error(transparent)]
+    Http(Arc<reqwest::Error>),
+    #[error("Request failed with HTTP status {0}")]
+    HttpStatus(u16),
+    #[error("Device responded with malformed body ({message}): {body}")]
+    MalformedResponse { message: String, body: String },
+    #[error(transparent)]
+    Json(Arc<serde_json::Error>),
+    #[error(transparent)]
+    Io(Arc<std::io::Error>),
 }
 
 impl Error {
-    fn from_api_error_code(code: usize) -> Self {
+    fn from_api_error_code(code: usize, message: Option<String>) -> Self {
         match code {
-            400 => Self::WrongParameters,
-            _ => panic!("Unexpected api error"),
+            400 => Self::WrongParameters(message),
+            401 => Self::Unauthorized(message),
+            404 => Self::UnsupportedEndpoint(message),
+            422 => Self::InvalidRequestParameters(message),
+            v => Self::Unknown(v, message),
         }
     }
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let message = match self {
-            Error::WrongParameters => "API errored with code 400, wrong parameters",
-        };
-        write!(f, "{}", message)
+/// Checks HTTP status of device response before its body is parsed.
+///
+/// Server errors mean that request failed before reaching device API, e.g. on proxy, so they're
+/// reported as `Error::HttpStatus`. Client errors are reported by device itself along with API
+/// error in body, so such responses are still parsed.
+pub(crate) fn check_status(status: reqwest::StatusCode) -> Result<(), Error> {
+    if status.is_server_error() {
+        return Err(Error::HttpStatus(status.as_u16()));
+    }
+    Ok(())
+}
+
+/// Parses device response body, beginning of the body is kept in error for debugging.
+///
+/// With `tracing` feature API error reported in body is logged here, since some endpoints map
+/// their codes on their own.
+pub(crate) fn parse_json<T: DeserializeOwned>(body: &[u8]) -> Result<T, Error> {
+    #[cfg(feature = "tracing")]
+    if let Ok(v) = serde_json::from_slice::<EmptyResponse>(body) {
+        if v.error != 0 {
+            tracing::warn!(code = v.error, message = ?v.message, "API errored");
+        }
+    }
+
+    serde_json::from_slice(body).map_err(|e| Error::MalformedResponse {
+        message: e.to_string(),
+        body: String::from_utf8_lossy(body)
+            .chars()
+            .take(BODY_SNIPPET_LEN)
+            .collect(),
+    })
+}
+
+/// Error for outlet missing in given per-outlet field of info.
+pub(crate) fn missing_outlet(name: &str, outlet: u8) -> Error {
+    Error::MalformedField {
+        name: name.to_string(),
+        message: format!("outlet {} is missing", outlet),
+    }
+}
+
+// Successful response without data, e.g. from flaky firmware
+fn missing_data() -> Error {
+    Error::UnexpectedResponse("data is missing".to_string())
+}
+
+// Reported message is preferred over generic description of documented code
+fn api_message(code: usize, message: &Option<String>, description: &str) -> String {
+    match message {
+        Some(v) => format!("API errored with code {}: {}", code, v),
+        None => format!("API errored with code {}, {}", code, description),
+    }
+}
+
+// Wrapped errors aren't comparable, so they're equal only if they're clones of the same error
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        use Error::*;
+
+        match (self, other) {
+            (WrongParameters(a), WrongParameters(b))
+            | (Unauthorized(a), Unauthorized(b))
+            | (UnsupportedEndpoint(a), UnsupportedEndpoint(b))
+            | (InvalidRequestParameters(a), InvalidRequestParameters(b)) => a == b,
+            (Unknown(a, a_message), Unknown(b, b_message)) => a == b && a_message == b_message,
+            (InvalidParameter(a), InvalidParameter(b))
+            | (UnexpectedSwitchPosition(a), UnexpectedSwitchPosition(b))
+            | (InvalidDeviceId(a), InvalidDeviceId(b))
+            | (InvalidSha256(a), InvalidSha256(b))
+            | (UnexpectedResponse(a), UnexpectedResponse(b)) => a == b,
+            (
+                FirmwareMismatch { expected, actual },
+                FirmwareMismatch {
+                    expected: b_expected,
+                    actual: b_actual,
+                },
+            ) => expected == b_expected && actual == b_actual,
+            (BudgetExceeded(a), BudgetExceeded(b)) | (TimedOut(a), TimedOut(b)) => a == b,
+            (
+                MalformedField { name, message },
+                MalformedField {
+                    name: b_name,
+                    message: b_message,
+                },
+            ) => name == b_name && message == b_message,
+            (OtaUnlockFailed(a), OtaUnlockFailed(b)) => a == b,
+            (OtaLocked, OtaLocked)
+            | (FirmwareDownloadTimedOut, FirmwareDownloadTimedOut)
+            | (FirmwareTooLarge, FirmwareTooLarge)
+            | (FirmwareDownloadFailed, FirmwareDownloadFailed)
+            | (FirmwareChecksumMismatch, FirmwareChecksumMismatch) => true,
+            (HttpStatus(a), HttpStatus(b)) => a == b,
+            (
+                MalformedResponse { message, body },
+                MalformedResponse {
+                    message: b_message,
+                    body: b_body,
+                },
+            ) => message == b_message && body == b_body,
+            (Http(a), Http(b)) => Arc::ptr_eq(a, b),
+            (Json(a), Json(b)) => Arc::ptr_eq(a, b),
+            (Io(a), Io(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(value: reqwest::Error) -> Self {
+        Error::Http(Arc::new(value))
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(value: serde_json::Error) -> Self {
+        Error::Json(Arc::new(value))
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Error::Io(Arc::new(value))
     }
 }
 
-impl std::error::Error for Error {}
+/// Allows to consume device errors in IO-centric code.
+///
+/// Errors caused by given parameters are mapped to `InvalidInput`, unauthorized requests to
+/// `PermissionDenied`, unsupported endpoints to `Unsupported`, errors caused by unexpected
+/// device state or reports are mapped to `InvalidData` and exceeded budget or timeout to
+/// `TimedOut`. Wrapped IO errors keep their kind, unparsable responses are mapped to
+/// `InvalidData` and failed requests to `Other`.
+impl From<Error> for std::io::Error {
+    fn from(value: Error) -> Self {
+        use std::io::ErrorKind;
+
+        let kind = match &value {
+            Error::WrongParameters(_)
+            | Error::InvalidParameter(_)
+            | Error::InvalidDeviceId(_)
+            | Error::InvalidRequestParameters(_)
+            | Error::InvalidSha256(_) => ErrorKind::InvalidInput,
+            Error::Unauthorized(_) => ErrorKind::PermissionDenied,
+            Error::UnsupportedEndpoint(_) => ErrorKind::Unsupported,
+            Error::UnexpectedSwitchPosition(_)
+            | Error::FirmwareMismatch { .. }
+            | Error::MalformedField { .. }
+            | Error::UnexpectedResponse(_)
+            | Error::Unknown(..)
+            | Error::OtaUnlockFailed(_)
+            | Error::OtaLocked
+            | Error::FirmwareTooLarge
+            | Error::FirmwareDownloadFailed
+            | Error::FirmwareChecksumMismatch
+            | Error::MalformedResponse { .. }
+            | Error::Json(_) => ErrorKind::InvalidData,
+            Error::BudgetExceeded(_) | Error::TimedOut(_) | Error::FirmwareDownloadTimedOut => {
+                ErrorKind::TimedOut
+            }
+            Error::Http(_) | Error::HttpStatus(_) => ErrorKind::Other,
+            Error::Io(v) => v.kind(),
+        };
+        std::io::Error::new(kind, value)
+    }
+}
 
 /// Represents switch position.
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+///
+/// `Unknown` holds raw value reported by device if it doesn't match any known position, e.g. some
+/// clone firmware reports `"unavailable"`. It's returned only by lenient clients, look at
+/// `ClientBuilder::lenient` for details.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SwitchPosition {
     On,
     Off,
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 /// Represents switch position on device startup.
 ///
 /// `Stay` means that device will use last known position on startup.
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum StartupPosition {
     On,
@@ -50,135 +308,1911 @@ pub enum StartupPosition {
     Stay,
 }
 
-#[derive(Deserialize, Serialize)]
-struct Switch {
-    switch: SwitchPosition,
-    outlet: u8,
+impl SwitchPosition {
+    /// Returns every known position, `Unknown` isn't included since it's not a single value.
+    pub fn all() -> [SwitchPosition; 2] {
+        [SwitchPosition::On, SwitchPosition::Off]
+    }
 }
 
-#[derive(Deserialize, Serialize)]
-struct Startup {
-    startup: StartupPosition,
-    outlet: u8,
+impl StartupPosition {
+    /// Returns every position.
+    pub fn all() -> [StartupPosition; 3] {
+        [
+            StartupPosition::On,
+            StartupPosition::Off,
+            StartupPosition::Stay,
+        ]
+    }
+}
+
+/// `true` means `On` and `false` means `Off`.
+impl From<bool> for SwitchPosition {
+    fn from(value: bool) -> Self {
+        if value {
+            SwitchPosition::On
+        } else {
+            SwitchPosition::Off
+        }
+    }
+}
+
+/// Parses `"on"` or `"off"`, other values are rejected with `Error::InvalidParameter`.
+///
+/// Unlike deserialization, it never produces `Unknown`, since it's intended for values given by
+/// user.
+impl TryFrom<&str> for SwitchPosition {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        SwitchPosition::from_wire_str(value)
+            .ok_or_else(|| Error::InvalidParameter(format!("unknown switch position {}", value)))
+    }
+}
+
+/// Same as `TryFrom<&str>`, but case-insensitive, e.g. to parse CLI arguments and configs.
+impl FromStr for SwitchPosition {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        SwitchPosition::from_wire_str(&value.to_ascii_lowercase())
+            .ok_or_else(|| Error::InvalidParameter(format!("unknown switch position {}", value)))
+    }
+}
+
+/// Formats position as it's represented in API, e.g. `"on"`.
+impl fmt::Display for SwitchPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_wire_str())
+    }
+}
+
+/// Parses `"on"`, `"off"` or `"stay"` case-insensitively, other values are rejected with
+/// `Error::InvalidParameter`.
+impl FromStr for StartupPosition {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        StartupPosition::from_wire_str(&value.to_ascii_lowercase())
+            .ok_or_else(|| Error::InvalidParameter(format!("unknown startup position {}", value)))
+    }
+}
+
+/// Formats position as it's represented in API, e.g. `"stay"`.
+impl fmt::Display for StartupPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_wire_str())
+    }
+}
+
+/// Startup position mirroring given switch position.
+///
+/// It never produces `Stay`. `SwitchPosition::Unknown` is converted to `Off`, since it's the
+/// safest position to start with.
+impl From<SwitchPosition> for StartupPosition {
+    fn from(value: SwitchPosition) -> Self {
+        match value {
+            SwitchPosition::On => StartupPosition::On,
+            SwitchPosition::Off | SwitchPosition::Unknown(_) => StartupPosition::Off,
+        }
+    }
+}
+
+/// Represents whether inching (pulse) mode is enabled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PulsePosition {
+    On,
+    Off,
+}
+
+/// Represents what inching (pulse) does.
+///
+/// `DelayOff` switches outlet off once pulse is over, `DelayOn` switches it on.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum PulseMode {
+    #[serde(rename = "off")]
+    DelayOff,
+    #[serde(rename = "on")]
+    DelayOn,
+}
+
+/// Common interface of positions accepted and reported by device.
+///
+/// Both `SwitchPosition` and `StartupPosition` share On/Off concept, this trait allows to handle
+/// them generically.
+pub trait DevicePosition: Sized {
+    /// Returns position as it's represented in API
+    fn as_wire_str(&self) -> &str;
+
+    /// Parses position from its API representation, returns `None` for unknown values
+    fn from_wire_str(value: &str) -> Option<Self>;
+}
+
+impl DevicePosition for SwitchPosition {
+    fn as_wire_str(&self) -> &str {
+        match self {
+            SwitchPosition::On => "on",
+            SwitchPosition::Off => "off",
+            SwitchPosition::Unknown(v) => v,
+        }
+    }
+
+    fn from_wire_str(value: &str) -> Option<Self> {
+        match value {
+            "on" => Some(SwitchPosition::On),
+            "off" => Some(SwitchPosition::Off),
+            _ => None,
+        }
+    }
+}
+
+impl DevicePosition for StartupPosition {
+    fn as_wire_str(&self) -> &str {
+        match self {
+            StartupPosition::On => "on",
+            StartupPosition::Off => "off",
+            StartupPosition::Stay => "stay",
+        }
+    }
+
+    fn from_wire_str(value: &str) -> Option<Self> {
+        match value {
+            "on" => Some(StartupPosition::On),
+            "off" => Some(StartupPosition::Off),
+            "stay" => Some(StartupPosition::Stay),
+            _ => None,
+        }
+    }
+}
+
+impl DevicePosition for PulsePosition {
+    fn as_wire_str(&self) -> &str {
+        match self {
+            PulsePosition::On => "on",
+            PulsePosition::Off => "off",
+        }
+    }
+
+    fn from_wire_str(value: &str) -> Option<Self> {
+        match value {
+            "on" => Some(PulsePosition::On),
+            "off" => Some(PulsePosition::Off),
+            _ => None,
+        }
+    }
+}
+
+/// Represents index of device outlet, it's guaranteed to be within supported range.
+///
+/// minir3 has 4 outlets, so `new` accepts `0..=3`. `with_max` allows to check against other
+/// max, e.g. last outlet reported by device. `Error::InvalidParameter` is returned for
+/// out-of-range index.
+///
+/// # Example
+///
+/// ```
+/// # use sonoff_minir3::OutletIndex;
+///
+/// assert_eq!(OutletIndex::new(2).unwrap().get(), 2);
+/// assert!(OutletIndex::new(4).is_err());
+/// assert!(OutletIndex::with_max(2, 1).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OutletIndex(u8);
+
+impl OutletIndex {
+    /// Index of the last outlet of minir3
+    pub const MAX: u8 = 3;
+
+    pub fn new(value: u8) -> Result<Self, Error> {
+        Self::with_max(value, Self::MAX)
+    }
+
+    pub fn with_max(value: u8, max: u8) -> Result<Self, Error> {
+        if value > max {
+            return Err(Error::InvalidParameter(format!(
+                "outlet should be within 0..={}, got {}",
+                max, value
+            )));
+        }
+        Ok(OutletIndex(value))
+    }
+
+    pub fn get(self) -> u8 {
+        self.0
+    }
+}
+
+impl TryFrom<u8> for OutletIndex {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl From<OutletIndex> for u8 {
+    fn from(value: OutletIndex) -> Self {
+        value.0
+    }
+}
+
+/// Represents switch position of specific outlet.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Switch {
+    pub switch: SwitchPosition,
+    pub outlet: u8,
+}
+
+/// Represents startup position of specific outlet.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Startup {
+    pub startup: StartupPosition,
+    pub outlet: u8,
+}
+
+/// Represents inching (pulse) settings of specific outlet.
+///
+/// `switch` - position outlet is switched to once pulse is over
+/// `width` - pulse duration as it's reported by device, look at `PulseWidth` for details
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Pulse {
+    pub pulse: PulsePosition,
+    pub switch: SwitchPosition,
+    pub outlet: u8,
+    pub width: PulseWidth,
+}
+
+/// Represents pulse duration as it's reported by device.
+///
+/// Firmware reports it as integer or string, in milliseconds or seconds depending on firmware,
+/// so raw value is kept and should be converted with `as_duration`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PulseWidth(pub u64);
+
+impl PulseWidth {
+    /// Converts raw value to duration, interpreting it in given unit.
+    pub fn as_duration(&self, unit: PulseWidthUnit) -> Duration {
+        match unit {
+            PulseWidthUnit::Milliseconds => Duration::from_millis(self.0),
+            PulseWidthUnit::Seconds => Duration::from_secs(self.0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PulseWidth {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error as _;
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Width {
+            Integer(u64),
+            String(String),
+        }
+
+        match Width::deserialize(deserializer)? {
+            Width::Integer(v) => Ok(PulseWidth(v)),
+            Width::String(v) => v
+                .trim()
+                .parse()
+                .map(PulseWidth)
+                .map_err(|_| D::Error::custom(format!("invalid pulse width {:?}", v))),
+        }
+    }
+}
+
+/// Represents unit firmware reports pulse width in.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum PulseWidthUnit {
+    #[default]
+    Milliseconds,
+    Seconds,
+}
+
+/// Represents URL scheme requests are sent with.
+///
+/// Devices accept only plain HTTP, so `Https` is useful only for devices behind HTTPS reverse
+/// proxy. It requires `rustls-tls` (enabled by default) or `native-tls` feature.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Scheme {
+    #[default]
+    Http,
+    Https,
+}
+
+impl Scheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scheme::Http => "http",
+            Scheme::Https => "https",
+        }
+    }
 }
 
 /// Represents device info.
 ///
 /// Currently only limited amount of details are supported.
-/// `switch` - current switch position on outlet 0
-/// `startup` - switch position on startup on outlet 0
-#[derive(Debug, PartialEq)]
+/// `switch` - current switch position on default outlet
+/// `startup` - switch position on startup on default outlet
+/// `status_led` - whether network status LED is enabled, firmware that doesn't report it is
+/// considered to have it enabled as it's by default
+/// `device_id` - id of device, empty if it's not reported
+/// `fw_version` - firmware version, empty if it's not reported
+/// `pulse` - inching settings of default outlet, if they're reported
+/// `sled_brightness` - brightness of network status LED in percents, if it's reported
+/// `ssid` - SSID of WiFi network device joined, if it's reported
+///
+/// It's serialized with field names as is, so it could be persisted and read back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Info {
     pub switch: SwitchPosition,
     pub startup: StartupPosition,
+    pub status_led: bool,
+    pub device_id: String,
+    pub fw_version: String,
+    #[serde(default)]
+    pub pulse: Option<Pulse>,
+    #[serde(default)]
+    pub sled_brightness: Option<u8>,
+    #[serde(default)]
+    pub ssid: Option<String>,
+}
+
+/// Represents command sent to device.
+///
+/// `endpoint` - API path command was sent to, e.g. `/zeroconf/switches`
+/// `body` - JSON body of the command
+/// `timestamp` - time when command was sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandRecord {
+    pub endpoint: String,
+    pub body: String,
+    pub timestamp: SystemTime,
 }
 
 #[derive(Deserialize)]
 pub(crate) struct InfoResponse {
-    data: Option<InfoData>,
+    data: Option<RawInfo>,
+    #[serde(deserialize_with = "deserialize_error_code")]
     error: usize,
+    #[serde(default, alias = "msg")]
+    message: Option<String>,
 }
 
-#[derive(Deserialize)]
-struct InfoData {
-    switches: Vec<Switch>,
-    configure: Vec<Startup>,
+/// Deserializes API error code.
+///
+/// Besides integers, some clone firmware reports code as string (`"0"`) or float (`0.0`). Floats
+/// are accepted only if they are integral.
+fn deserialize_error_code<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error as _;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Code {
+        Integer(usize),
+        Float(f64),
+        String(String),
+    }
+
+    match Code::deserialize(deserializer)? {
+        Code::Integer(v) => Ok(v),
+        Code::Float(v) if v >= 0.0 && v.fract() == 0.0 => Ok(v as usize),
+        Code::Float(v) => Err(D::Error::custom(format!("invalid error code {}", v))),
+        Code::String(v) => v
+            .parse()
+            .map_err(|_| D::Error::custom(format!("invalid error code {:?}", v))),
+    }
 }
 
-impl TryFrom<InfoResponse> for Info {
-    type Error = Error;
+/// Represents device info as it's reported by `/zeroconf/info` API.
+///
+/// Unlike `Info` it contains details for all outlets. Fields that aren't reported by every
+/// firmware are optional.
+/// `free_heap` - free heap memory in bytes, reported by some ESP-based firmware
+/// `fault_count` and `last_error` - fault counter and last fault description, reported by some
+/// firmware
+/// `ip_mode` - whether device obtained its IP via DHCP or uses static one, reported by some
+/// firmware
+/// `sled_brightness` - brightness of network status LED in percents, reported by some firmware
+/// `power_watts` - instantaneous power consumption in watts, reported by variants with metering
+/// `outlet_names` - user-assigned labels of outlets, reported by some firmware
+/// `pulses` - inching settings of outlets, reported by multi-channel firmware
+/// `pulse_width` - inching duration, reported by single-channel firmware instead of `pulses`
+/// `button_press_count` and `last_press_time` - number of physical button presses and unix
+/// timestamp of the last one, reported by some firmware
+/// `pairing` - whether device is advertising for pairing, reported by some firmware
+/// `wifi_security` - security mode of WiFi network device joined, reported by some firmware
+/// `reset_reason` - cause of the last reset, reported by some ESP-based firmware
+/// `ntp_server` - time server device synchronizes its clock with, reported by some firmware
+/// `ssid` - SSID of WiFi network device joined, reported by some firmware
+/// `sled_online` - whether network status LED is enabled
+/// `device_id` - id of device, which is also used in its mDNS name and DIY AP SSID
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawInfo {
+    pub switches: Vec<Switch>,
+    pub configure: Vec<Startup>,
+    #[serde(default)]
+    pub pulses: Option<Vec<Pulse>>,
+    #[serde(default)]
+    pub pulse_width: Option<PulseWidth>,
+    #[serde(default)]
+    pub fw_version: Option<String>,
+    #[serde(default, rename = "deviceid")]
+    pub device_id: Option<String>,
+    #[serde(default)]
+    pub sta_mac: Option<String>,
+    #[serde(default)]
+    pub rssi: Option<i32>,
+    #[serde(default)]
+    pub bssid: Option<String>,
+    #[serde(default)]
+    pub ssid: Option<String>,
+    #[serde(default)]
+    pub free_heap: Option<u32>,
+    #[serde(default)]
+    pub fault_count: Option<u32>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub ip_mode: Option<IpMode>,
+    #[serde(default)]
+    pub sled_brightness: Option<u8>,
+    #[serde(default, rename = "power")]
+    pub power_watts: Option<f64>,
+    #[serde(default)]
+    pub outlet_names: Option<Vec<OutletName>>,
+    #[serde(default)]
+    pub button_press_count: Option<u32>,
+    #[serde(default)]
+    pub last_press_time: Option<u64>,
+    #[serde(default)]
+    pub pairing: Option<bool>,
+    #[serde(default)]
+    pub wifi_security: Option<WifiSecurity>,
+    #[serde(default)]
+    pub reset_reason: Option<ResetReason>,
+    #[serde(default)]
+    pub ntp_server: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_on_off")]
+    pub sled_online: Option<bool>,
+}
 
-    fn try_from(value: InfoResponse) -> Result<Self, Self::Error> {
-        match value.error {
-            0 => {
-                let data = value.data.unwrap();
-                Ok(Self {
-                    switch: data
-                        .switches
-                        .into_iter()
-                        .find(|s| s.outlet == OUTLET2USE)
-                        .unwrap()
-                        .switch,
-                    startup: data
-                        .configure
-                        .into_iter()
-                        .find(|s| s.outlet == OUTLET2USE)
-                        .unwrap()
-                        .startup,
-                })
-            }
-            v => Err(Error::from_api_error_code(v)),
-        }
+fn deserialize_on_off<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error as _;
+
+    match String::deserialize(deserializer)?.as_str() {
+        "on" => Ok(Some(true)),
+        "off" => Ok(Some(false)),
+        v => Err(D::Error::custom(format!("invalid LED position {:?}", v))),
     }
 }
 
-#[derive(Serialize)]
-pub(crate) struct StartupsRequest {
-    data: StartupsData,
+/// Represents label assigned to outlet.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct OutletName {
+    pub outlet: u8,
+    pub name: String,
 }
 
-#[derive(Serialize)]
-struct StartupsData {
-    configure: Vec<Startup>,
+/// Represents current and startup switch positions of single outlet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutletSnapshot {
+    pub outlet: u8,
+    pub switch: SwitchPosition,
+    pub startup: StartupPosition,
 }
 
-impl From<StartupPosition> for StartupsRequest {
-    fn from(value: StartupPosition) -> Self {
-        let mut startups = vec![Startup {
-            startup: value,
-            outlet: OUTLET2USE,
-        }];
+/// Represents features supported by device firmware.
+///
+/// Capabilities are derived from details reported by `/zeroconf/info` API and, if firmware
+/// supports it, from list of commands reported by `/zeroconf/commands` API.
+/// `fw_version` - firmware version capabilities were determined for
+/// `multi_outlet` - device has more than one outlet
+/// `pulse` - firmware supports inching
+/// `sled_brightness` - firmware supports setting LED brightness
+/// `power_metering` - device reports power consumption
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capabilities {
+    pub fw_version: Option<String>,
+    pub multi_outlet: bool,
+    pub pulse: bool,
+    pub sled_brightness: bool,
+    pub power_metering: bool,
+}
 
-        for i in 1..=3 {
-            startups.push(Startup {
-                startup: StartupPosition::Off,
-                outlet: i,
-            })
+impl From<&RawInfo> for Capabilities {
+    fn from(value: &RawInfo) -> Self {
+        Capabilities {
+            fw_version: value.fw_version.clone(),
+            multi_outlet: value.switches.len() > 1,
+            pulse: value.pulses.is_some(),
+            sled_brightness: value.sled_brightness.is_some(),
+            power_metering: value.power_watts.is_some(),
         }
+    }
+}
 
-        Self {
-            data: StartupsData {
-                configure: startups,
-            },
+impl Capabilities {
+    /// Refines capabilities with list of commands reported by device itself.
+    ///
+    /// Unlike info-based heuristics, it tells whether firmware supports command even when it
+    /// doesn't report related details.
+    pub(crate) fn with_supported_commands(self, commands: &[String]) -> Self {
+        let supports = |command: &str| commands.iter().any(|v| v == command);
+        Capabilities {
+            pulse: supports("pulse"),
+            sled_brightness: supports("sledBrightness"),
+            ..self
         }
     }
 }
 
-#[derive(Serialize)]
-pub(crate) struct SwitchesRequest {
-    data: SwitchesData,
+#[derive(Deserialize)]
+pub(crate) struct SupportedCommandsResponse {
+    data: Option<SupportedCommands>,
+    #[serde(deserialize_with = "deserialize_error_code")]
+    error: usize,
+    #[serde(default, alias = "msg")]
+    message: Option<String>,
 }
 
-#[derive(Serialize)]
-struct SwitchesData {
-    switches: Vec<Switch>,
+#[derive(Deserialize)]
+struct SupportedCommands {
+    commands: Option<Vec<String>>,
 }
 
-impl From<SwitchPosition> for SwitchesRequest {
-    fn from(value: SwitchPosition) -> Self {
-        SwitchesRequest {
-            data: SwitchesData {
-                switches: vec![Switch {
-                    switch: value,
-                    outlet: OUTLET2USE,
-                }],
-            },
+impl TryFrom<SupportedCommandsResponse> for Vec<String> {
+    type Error = Error;
+
+    fn try_from(value: SupportedCommandsResponse) -> Result<Self, Self::Error> {
+        match value.error {
+            0 => value.data.and_then(|v| v.commands).ok_or_else(missing_data),
+            v => Err(Error::from_api_error_code(v, value.message)),
         }
     }
 }
 
 #[derive(Deserialize)]
-pub(crate) struct EmptyResponse {
+pub(crate) struct SignalStrengthResponse {
+    data: Option<SignalStrength>,
+    #[serde(deserialize_with = "deserialize_error_code")]
     error: usize,
+    #[serde(default, alias = "msg")]
+    message: Option<String>,
 }
 
-impl TryFrom<EmptyResponse> for () {
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignalStrength {
+    signal_strength: i32,
+}
+
+/// Represents WiFi signal strength (RSSI) in dBm.
+///
+/// It's displayed with unit, e.g. `-67 dBm`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Rssi(pub i32);
+
+impl Rssi {
+    /// Returns signal strength in dBm.
+    pub fn dbm(&self) -> i32 {
+        self.0
+    }
+
+    /// Returns signal quality in percent estimated from signal strength.
+    ///
+    /// Common heuristic is used: -100 dBm and weaker is 0%, -50 dBm and stronger is 100% and
+    /// linear in between.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use sonoff_minir3::Rssi;
+    ///
+    /// assert_eq!(Rssi(-67).quality_percent(), 66);
+    /// ```
+    pub fn quality_percent(&self) -> u8 {
+        (2 * (self.0.clamp(-100, -50) + 100)) as u8
+    }
+}
+
+impl fmt::Display for Rssi {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} dBm", self.0)
+    }
+}
+
+impl TryFrom<SignalStrengthResponse> for Rssi {
     type Error = Error;
 
-    fn try_from(value: EmptyResponse) -> Result<Self, Self::Error> {
+    fn try_from(value: SignalStrengthResponse) -> Result<Self, Self::Error> {
         match value.error {
-            0 => Ok(()),
-            v => Err(Error::from_api_error_code(v)),
+            0 => Ok(Rssi(value.data.ok_or_else(missing_data)?.signal_strength)),
+            v => Err(Error::from_api_error_code(v, value.message)),
+        }
+    }
+}
+
+/// Represents security mode of WiFi network.
+///
+/// `Unknown` holds raw value reported by device if it doesn't match any known mode.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WifiSecurity {
+    Open,
+    Wep,
+    Wpa,
+    Wpa2,
+    Wpa3,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+/// Represents cause of the last device reset.
+///
+/// `Unknown` holds raw value reported by device if it doesn't match any known cause.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetReason {
+    PowerOn,
+    HardwareWatchdog,
+    Exception,
+    SoftwareWatchdog,
+    SoftwareRestart,
+    DeepSleepWake,
+    External,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+impl ResetReason {
+    /// Whether device was reset because of crash, repeated crash resets mean crash loop.
+    pub fn is_crash(&self) -> bool {
+        matches!(
+            self,
+            ResetReason::HardwareWatchdog | ResetReason::Exception | ResetReason::SoftwareWatchdog
+        )
+    }
+}
+
+/// Represents how device obtained its IP address.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpMode {
+    Dhcp,
+    Static,
+}
+
+/// Represents faults reported by device.
+///
+/// Both fields are `None` if firmware doesn't report faults.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Faults {
+    pub count: Option<u32>,
+    pub last_error: Option<String>,
+}
+
+impl From<RawInfo> for Faults {
+    fn from(value: RawInfo) -> Self {
+        Faults {
+            count: value.fault_count,
+            last_error: value.last_error,
+        }
+    }
+}
+
+impl TryFrom<InfoResponse> for RawInfo {
+    type Error = Error;
+
+    fn try_from(value: InfoResponse) -> Result<Self, Self::Error> {
+        match value.error {
+            0 => value.data.ok_or_else(missing_data),
+            v => Err(Error::from_api_error_code(v, value.message)),
+        }
+    }
+}
+
+/// FNV-1a hasher used for config fingerprints.
+///
+/// Unlike std hashers its output is guaranteed to be stable, so fingerprints could be stored and
+/// compared later.
+struct Fingerprint(u64);
+
+impl Fingerprint {
+    fn new() -> Self {
+        Fingerprint(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> &mut Self {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+        // Separator, so adjacent fields don't mix
+        self.0 ^= 0xff;
+        self.0 = self.0.wrapping_mul(0x100000001b3);
+        self
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Info {
+    /// Returns stable fingerprint of persistent device configuration.
+    ///
+    /// Startup position, pulse settings, LED settings and WiFi SSID are taken into account, while
+    /// current switch position isn't a configuration.
+    pub fn config_fingerprint(&self) -> u64 {
+        let mut fingerprint = Fingerprint::new();
+        fingerprint
+            .write(b"startup")
+            .write(self.startup.as_wire_str().as_bytes());
+        if let Some(v) = &self.pulse {
+            write_pulse(&mut fingerprint, v);
+        }
+        if let Some(v) = self.sled_brightness {
+            fingerprint.write(b"sled_brightness").write(&[v]);
+        }
+        fingerprint
+            .write(b"sled_online")
+            .write(&[self.status_led as u8]);
+        if let Some(v) = &self.ssid {
+            fingerprint.write(b"ssid").write(v.as_bytes());
+        }
+
+        fingerprint.finish()
+    }
+}
+
+fn write_pulse(fingerprint: &mut Fingerprint, pulse: &Pulse) {
+    fingerprint
+        .write(b"pulse")
+        .write(&[pulse.outlet])
+        .write(pulse.pulse.as_wire_str().as_bytes())
+        .write(pulse.switch.as_wire_str().as_bytes())
+        .write(&pulse.width.0.to_le_bytes());
+}
+
+impl RawInfo {
+    /// Returns stable fingerprint of persistent device configuration.
+    ///
+    /// Startup positions and pulse settings of all outlets, LED settings and WiFi SSID are taken
+    /// into account, while current switch positions and other network details are not.
+    pub fn config_fingerprint(&self) -> u64 {
+        let mut fingerprint = Fingerprint::new();
+
+        let mut configure: Vec<_> = self.configure.iter().collect();
+        configure.sort_by_key(|v| v.outlet);
+        for v in configure {
+            fingerprint
+                .write(b"startup")
+                .write(&[v.outlet])
+                .write(v.startup.as_wire_str().as_bytes());
+        }
+
+        if let Some(pulses) = &self.pulses {
+            let mut pulses: Vec<_> = pulses.iter().collect();
+            pulses.sort_by_key(|v| v.outlet);
+            for v in pulses {
+                write_pulse(&mut fingerprint, v);
+            }
+        }
+
+        if let Some(v) = self.sled_brightness {
+            fingerprint.write(b"sled_brightness").write(&[v]);
+        }
+        if let Some(v) = self.sled_online {
+            fingerprint.write(b"sled_online").write(&[v as u8]);
+        }
+        if let Some(v) = &self.ssid {
+            fingerprint.write(b"ssid").write(v.as_bytes());
+        }
+
+        fingerprint.finish()
+    }
+
+    /// Returns current and startup switch positions of every outlet.
+    ///
+    /// `switches` and `configure` are joined by outlet, outlets missing in any of them are
+    /// skipped.
+    pub fn snapshot(&self) -> Vec<OutletSnapshot> {
+        self.switches
+            .iter()
+            .filter_map(|s| {
+                self.configure
+                    .iter()
+                    .find(|c| c.outlet == s.outlet)
+                    .map(|c| OutletSnapshot {
+                        outlet: s.outlet,
+                        switch: s.switch.clone(),
+                        startup: c.startup.clone(),
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns outlet labeled with given name.
+    ///
+    /// `None` is returned if there is no such label or firmware doesn't report labels at all.
+    pub fn outlet_by_name(&self, name: &str) -> Option<u8> {
+        self.outlet_names
+            .as_ref()?
+            .iter()
+            .find(|v| v.name == name)
+            .map(|v| v.outlet)
+    }
+}
+
+impl Info {
+    /// Info of given outlet, `Error::MalformedField` is returned if device didn't report it.
+    pub(crate) fn for_outlet(data: RawInfo, outlet: u8) -> Result<Self, Error> {
+        Ok(Self {
+            switch: data
+                .switches
+                .into_iter()
+                .find(|s| s.outlet == outlet)
+                .ok_or_else(|| missing_outlet("switches", outlet))?
+                .switch,
+            startup: data
+                .configure
+                .into_iter()
+                .find(|s| s.outlet == outlet)
+                .ok_or_else(|| missing_outlet("configure", outlet))?
+                .startup,
+            status_led: data.sled_online.unwrap_or(true),
+            device_id: data.device_id.unwrap_or_default(),
+            fw_version: data.fw_version.unwrap_or_default(),
+            pulse: data
+                .pulses
+                .and_then(|v| v.into_iter().find(|p| p.outlet == outlet)),
+            sled_brightness: data.sled_brightness,
+            ssid: data.ssid,
+        })
+    }
+}
+
+/// Represents device info where every field is parsed independently.
+///
+/// Unlike `Info`, one malformed field doesn't prevent reading the rest, it's reported as
+/// `Error::MalformedField` instead.
+/// `switch` - current switch position on default outlet
+/// `startup` - switch position on startup on default outlet
+/// `pulse_width` - inching duration, `None` if not reported
+/// `fw_version` - firmware version, `None` if not reported
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialInfo {
+    pub switch: Result<SwitchPosition, Error>,
+    pub startup: Result<StartupPosition, Error>,
+    pub pulse_width: Result<Option<PulseWidth>, Error>,
+    pub fw_version: Result<Option<String>, Error>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct PartialInfoResponse {
+    data: Option<serde_json::Value>,
+    #[serde(deserialize_with = "deserialize_error_code")]
+    error: usize,
+    #[serde(default, alias = "msg")]
+    message: Option<String>,
+}
+
+impl PartialInfo {
+    /// Info of given outlet, missing outlet is reported as `Error::MalformedField` of the field.
+    pub(crate) fn for_outlet(value: PartialInfoResponse, outlet: u8) -> Result<Self, Error> {
+        if value.error != 0 {
+            return Err(Error::from_api_error_code(value.error, value.message));
+        }
+        let data = value.data.unwrap_or_default();
+        Ok(PartialInfo {
+            switch: parse_outlet_field(&data, outlet, "switches", "switch"),
+            startup: parse_outlet_field(&data, outlet, "configure", "startup"),
+            pulse_width: parse_optional_field(&data, "pulseWidth"),
+            fw_version: parse_optional_field(&data, "fwVersion"),
+        })
+    }
+}
+
+fn parse_field<T: DeserializeOwned>(name: &str, value: &serde_json::Value) -> Result<T, Error> {
+    T::deserialize(value).map_err(|e| Error::MalformedField {
+        name: name.to_string(),
+        message: e.to_string(),
+    })
+}
+
+fn parse_optional_field<T: DeserializeOwned>(
+    data: &serde_json::Value,
+    name: &str,
+) -> Result<Option<T>, Error> {
+    data.get(name).map(|v| parse_field(name, v)).transpose()
+}
+
+// Parses `name` of `outlet` entry in `list`, other entries aren't touched
+fn parse_outlet_field<T: DeserializeOwned>(
+    data: &serde_json::Value,
+    outlet: u8,
+    list: &str,
+    name: &str,
+) -> Result<T, Error> {
+    let entry = data
+        .get(list)
+        .and_then(|v| v.as_array())
+        .and_then(|v| {
+            v.iter()
+                .find(|e| e.get("outlet").and_then(|v| v.as_u64()) == Some(outlet as u64))
+        })
+        .ok_or_else(|| missing_outlet(list, outlet))?;
+    parse_field(name, entry.get(name).unwrap_or(&serde_json::Value::Null))
+}
+
+#[derive(Serialize)]
+pub(crate) struct StartupsRequest {
+    data: StartupsData,
+}
+
+#[derive(Serialize)]
+struct StartupsData {
+    configure: Vec<Startup>,
+}
+
+impl StartupsRequest {
+    /// Request setting given position for each given outlet only.
+    pub(crate) fn for_positions(positions: &[(OutletIndex, StartupPosition)]) -> Self {
+        Self {
+            data: StartupsData {
+                configure: positions
+                    .iter()
+                    .map(|(outlet, startup)| Startup {
+                        startup: startup.clone(),
+                        outlet: outlet.get(),
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct SwitchesRequest {
+    data: SwitchesData,
+}
+
+#[derive(Serialize)]
+struct SwitchesData {
+    switches: Vec<Switch>,
+}
+
+impl SwitchesRequest {
+    /// Request setting given position for each given outlet only.
+    pub(crate) fn for_positions(positions: &[(OutletIndex, SwitchPosition)]) -> Self {
+        SwitchesRequest {
+            data: SwitchesData {
+                switches: positions
+                    .iter()
+                    .map(|(outlet, switch)| Switch {
+                        switch: switch.clone(),
+                        outlet: outlet.get(),
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+/// Request for `/zeroconf/switch` API used by older single-channel firmware.
+#[derive(Serialize)]
+pub(crate) struct SwitchRequest {
+    data: SwitchData,
+}
+
+#[derive(Serialize)]
+struct SwitchData {
+    switch: SwitchPosition,
+}
+
+impl From<SwitchPosition> for SwitchRequest {
+    fn from(value: SwitchPosition) -> Self {
+        SwitchRequest {
+            data: SwitchData { switch: value },
+        }
+    }
+}
+
+// Pre-serialized `SwitchesRequest` bodies of outlet 0 for known positions, so frequent toggling doesn't
+// allocate and serialize the same request over and over.
+const SWITCHES_ON_BODY: &[u8] = b"{\"data\":{\"switches\":[{\"switch\":\"on\",\"outlet\":0}]}}";
+const SWITCHES_OFF_BODY: &[u8] = b"{\"data\":{\"switches\":[{\"switch\":\"off\",\"outlet\":0}]}}";
+
+impl SwitchesRequest {
+    /// Serialized request body for given position of outlet 0.
+    ///
+    /// Known positions are served from static bodies, `Unknown` falls back to serde.
+    pub(crate) fn body(position: SwitchPosition) -> Cow<'static, [u8]> {
+        match position {
+            SwitchPosition::On => Cow::Borrowed(SWITCHES_ON_BODY),
+            SwitchPosition::Off => Cow::Borrowed(SWITCHES_OFF_BODY),
+            v => Cow::Owned(Self::serialize(v)),
+        }
+    }
+
+    pub(crate) fn serialize(position: SwitchPosition) -> Vec<u8> {
+        serde_json::to_vec(&Self::for_positions(&[(FIRST_OUTLET, position)])).unwrap()
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct SledBrightnessRequest {
+    data: SledBrightnessData,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SledBrightnessData {
+    sled_brightness: u8,
+}
+
+impl TryFrom<u8> for SledBrightnessRequest {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value > 100 {
+            return Err(Error::InvalidParameter(format!(
+                "LED brightness should be within 0..=100, got {}",
+                value
+            )));
+        }
+        Ok(SledBrightnessRequest {
+            data: SledBrightnessData {
+                sled_brightness: value,
+            },
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct PulseRequest {
+    data: PulseData,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PulseData {
+    pulse: PulsePosition,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pulse_width: Option<u128>,
+    outlet: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    switch: Option<PulseMode>,
+}
+
+impl PulseRequest {
+    /// Request setting pulse on given outlet, mode is left as is on device if not given.
+    ///
+    /// Firmware accepts only widths that are multiple of 500ms within 500ms..=10h.
+    pub(crate) fn new(
+        outlet: OutletIndex,
+        pulse: PulsePosition,
+        width: Duration,
+        mode: Option<PulseMode>,
+    ) -> Result<Self, Error> {
+        let millis = width.as_millis();
+        if !width
+            .as_nanos()
+            .is_multiple_of(PULSE_WIDTH_STEP_MS * 1_000_000)
+            || !(PULSE_WIDTH_STEP_MS..=MAX_PULSE_WIDTH_MS).contains(&millis)
+        {
+            return Err(Error::InvalidParameter(format!(
+                "pulse width should be a multiple of 500ms within 500..={}ms, got {}ms",
+                MAX_PULSE_WIDTH_MS,
+                width.as_nanos() as f64 / 1_000_000.0
+            )));
+        }
+        Ok(PulseRequest {
+            data: PulseData {
+                pulse,
+                pulse_width: Some(millis),
+                outlet: outlet.get(),
+                switch: mode,
+            },
+        })
+    }
+
+    /// Request disabling pulse on given outlet, which also aborts active countdown.
+    pub(crate) fn cancel(outlet: u8) -> Self {
+        PulseRequest {
+            data: PulseData {
+                pulse: PulsePosition::Off,
+                pulse_width: None,
+                outlet,
+                switch: None,
+            },
+        }
+    }
+}
+
+#[cfg(feature = "ota")]
+#[derive(Serialize)]
+pub(crate) struct OtaUnlockRequest<'a> {
+    deviceid: &'a str,
+    data: EmptyData,
+}
+
+#[cfg(feature = "ota")]
+#[derive(Serialize)]
+struct EmptyData {}
+
+#[cfg(feature = "ota")]
+impl<'a> OtaUnlockRequest<'a> {
+    pub(crate) fn new(device_id: &'a str) -> Self {
+        OtaUnlockRequest {
+            deviceid: device_id,
+            data: EmptyData {},
+        }
+    }
+}
+
+#[cfg(feature = "ota")]
+#[derive(Serialize)]
+pub(crate) struct OtaFlashRequest<'a> {
+    data: OtaFlashData<'a>,
+}
+
+#[cfg(feature = "ota")]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OtaFlashData<'a> {
+    download_url: &'a str,
+    sha256sum: &'a str,
+}
+
+#[cfg(feature = "ota")]
+impl<'a> OtaFlashRequest<'a> {
+    /// Checksum should be 64 hex digits, otherwise `Error::InvalidSha256` is returned.
+    pub(crate) fn new(download_url: &'a str, sha256sum: &'a str) -> Result<Self, Error> {
+        if sha256sum.len() != 64 || !sha256sum.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Error::InvalidSha256(sha256sum.to_string()));
+        }
+        Ok(OtaFlashRequest {
+            data: OtaFlashData {
+                download_url,
+                sha256sum,
+            },
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct StatusLedRequest {
+    data: StatusLedData,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusLedData {
+    sled_online: &'static str,
+}
+
+impl From<bool> for StatusLedRequest {
+    fn from(value: bool) -> Self {
+        StatusLedRequest {
+            data: StatusLedData {
+                sled_online: if value { "on" } else { "off" },
+            },
+        }
+    }
+}
+
+#[cfg(feature = "wifi")]
+#[derive(Serialize)]
+pub(crate) struct WifiRequest<'a> {
+    data: WifiData<'a>,
+}
+
+#[cfg(feature = "wifi")]
+#[derive(Serialize)]
+struct WifiData<'a> {
+    ssid: &'a str,
+    password: &'a str,
+}
+
+#[cfg(feature = "wifi")]
+impl<'a> WifiRequest<'a> {
+    pub(crate) fn new(ssid: &'a str, password: &'a str) -> Self {
+        WifiRequest {
+            data: WifiData { ssid, password },
+        }
+    }
+}
+
+/// Represents result of setting WiFi credentials.
+///
+/// `Accepted` means that device is going to join given network, `Rejected` holds the reason why
+/// device refused to do it.
+#[cfg(feature = "wifi")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum WifiSetResult {
+    Accepted,
+    Rejected(Error),
+}
+
+#[cfg(feature = "wifi")]
+impl From<EmptyResponse> for WifiSetResult {
+    fn from(value: EmptyResponse) -> Self {
+        match value.error {
+            0 => WifiSetResult::Accepted,
+            v => WifiSetResult::Rejected(Error::from_api_error_code(v, value.message)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct EmptyResponse {
+    #[serde(deserialize_with = "deserialize_error_code")]
+    error: usize,
+    #[serde(default, alias = "msg")]
+    message: Option<String>,
+}
+
+impl EmptyResponse {
+    /// Result of `/zeroconf/pulse` cancelling, which reports that there is no active pulse with
+    /// code 409.
+    pub(crate) fn into_cancel_pulse_result(self) -> Result<(), Error> {
+        match self.error {
+            0 | 409 => Ok(()),
+            v => Err(Error::from_api_error_code(v, self.message)),
+        }
+    }
+
+    /// Result of `/zeroconf/ota_unlock`, which reports device id mismatch with code 404.
+    #[cfg(feature = "ota")]
+    pub(crate) fn into_ota_unlock_result(self, device_id: &str) -> Result<(), Error> {
+        match self.error {
+            0 => Ok(()),
+            400 => Err(Error::WrongParameters(self.message)),
+            404 => Err(Error::InvalidDeviceId(device_id.to_string())),
+            v => Err(Error::OtaUnlockFailed(v)),
+        }
+    }
+
+    /// Result of `/zeroconf/ota_flash`, which reports every failure with its own code.
+    #[cfg(feature = "ota")]
+    pub(crate) fn into_ota_flash_result(self) -> Result<(), Error> {
+        match self.error {
+            0 => Ok(()),
+            403 => Err(Error::OtaLocked),
+            408 => Err(Error::FirmwareDownloadTimedOut),
+            413 => Err(Error::FirmwareTooLarge),
+            424 => Err(Error::FirmwareDownloadFailed),
+            471 => Err(Error::FirmwareChecksumMismatch),
+            v => Err(Error::from_api_error_code(v, self.message)),
+        }
+    }
+}
+
+impl TryFrom<EmptyResponse> for () {
+    type Error = Error;
+
+    fn try_from(value: EmptyResponse) -> Result<Self, Self::Error> {
+        match value.error {
+            0 => Ok(()),
+            v => Err(Error::from_api_error_code(v, value.message)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    mod io_error {
+        use super::*;
+        use rstest::rstest;
+        use std::io::ErrorKind;
+
+        #[rstest]
+        #[case::wrong_parameters(Error::WrongParameters(None), ErrorKind::InvalidInput)]
+        #[case::unauthorized(Error::Unauthorized(None), ErrorKind::PermissionDenied)]
+        #[case::unsupported_endpoint(Error::UnsupportedEndpoint(None), ErrorKind::Unsupported)]
+        #[case::invalid_request_parameters(
+            Error::InvalidRequestParameters(None),
+            ErrorKind::InvalidInput
+        )]
+        #[case::unknown(Error::Unknown(503, None), ErrorKind::InvalidData)]
+        #[case::invalid_parameter(
+            Error::InvalidParameter("brightness".to_string()),
+            ErrorKind::InvalidInput
+        )]
+        #[case::unexpected_switch_position(
+            Error::UnexpectedSwitchPosition("unavailable".to_string()),
+            ErrorKind::InvalidData
+        )]
+        #[case::invalid_device_id(Error::InvalidDeviceId("xyz".to_string()), ErrorKind::InvalidInput)]
+        #[case::firmware_mismatch(
+            Error::FirmwareMismatch {
+                expected: "1.4.3".to_string(),
+                actual: None,
+            },
+            ErrorKind::InvalidData
+        )]
+        #[case::malformed_field(
+            Error::MalformedField {
+                name: "pulseWidth".to_string(),
+                message: "invalid pulse width".to_string(),
+            },
+            ErrorKind::InvalidData
+        )]
+        #[case::unexpected_response(
+            Error::UnexpectedResponse("data is missing".to_string()),
+            ErrorKind::InvalidData
+        )]
+        #[case::ota_unlock_failed(Error::OtaUnlockFailed(500), ErrorKind::InvalidData)]
+        #[case::invalid_sha256(Error::InvalidSha256("xyz".to_string()), ErrorKind::InvalidInput)]
+        #[case::ota_locked(Error::OtaLocked, ErrorKind::InvalidData)]
+        #[case::firmware_download_timed_out(Error::FirmwareDownloadTimedOut, ErrorKind::TimedOut)]
+        #[case::budget_exceeded(Error::BudgetExceeded(Duration::from_secs(1)), ErrorKind::TimedOut)]
+        #[case::timed_out(Error::TimedOut(Duration::from_secs(1)), ErrorKind::TimedOut)]
+        #[case::json(
+            Error::from(serde_json::from_str::<serde_json::Value>("").unwrap_err()),
+            ErrorKind::InvalidData
+        )]
+        #[case::io(
+            Error::from(std::io::Error::from(ErrorKind::ConnectionRefused)),
+            ErrorKind::ConnectionRefused
+        )]
+        #[case::http_status(Error::HttpStatus(502), ErrorKind::Other)]
+        fn mapped_to_expected_kind(#[case] error: Error, #[case] expected: ErrorKind) {
+            let got = std::io::Error::from(error.clone());
+
+            assert_eq!(got.kind(), expected);
+            assert_eq!(
+                got.into_inner().unwrap().downcast::<Error>().unwrap(),
+                Box::new(error)
+            );
+        }
+    }
+
+    mod device_position {
+        use super::*;
+
+        fn round_trip<P: DevicePosition + PartialEq + fmt::Debug>(position: P) {
+            assert_eq!(P::from_wire_str(position.as_wire_str()), Some(position))
+        }
+
+        #[test]
+        fn round_trips_switch_positions() {
+            round_trip(SwitchPosition::On);
+            round_trip(SwitchPosition::Off);
+        }
+
+        #[test]
+        fn round_trips_startup_positions() {
+            round_trip(StartupPosition::On);
+            round_trip(StartupPosition::Off);
+            round_trip(StartupPosition::Stay);
+        }
+
+        #[test]
+        fn returns_none_for_unknown_values() {
+            assert_eq!(SwitchPosition::from_wire_str("unavailable"), None);
+            assert_eq!(StartupPosition::from_wire_str("unavailable"), None);
+        }
+    }
+
+    mod outlet_index {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::first(0)]
+        #[case::last(3)]
+        fn returns_expected_result(#[case] value: u8) {
+            assert_eq!(OutletIndex::new(value).unwrap().get(), value);
+            assert_eq!(u8::from(OutletIndex::try_from(value).unwrap()), value);
+        }
+
+        #[test]
+        fn errored_on_out_of_range() {
+            assert_eq!(
+                OutletIndex::new(4),
+                Err(Error::InvalidParameter(
+                    "outlet should be within 0..=3, got 4".to_string()
+                ))
+            );
+        }
+
+        #[test]
+        fn checked_against_given_max() {
+            assert!(OutletIndex::with_max(1, 1).is_ok());
+            assert_eq!(
+                OutletIndex::with_max(2, 1),
+                Err(Error::InvalidParameter(
+                    "outlet should be within 0..=1, got 2".to_string()
+                ))
+            );
+        }
+    }
+
+    mod all_positions {
+        use super::*;
+
+        fn assert_each_once<P: PartialEq + fmt::Debug>(got: &[P], expected: &[P]) {
+            assert_eq!(got.len(), expected.len());
+            for v in expected {
+                assert_eq!(got.iter().filter(|g| *g == v).count(), 1, "{:?}", v);
+            }
+        }
+
+        #[test]
+        fn lists_each_switch_position_once() {
+            assert_each_once(
+                &SwitchPosition::all(),
+                &[SwitchPosition::On, SwitchPosition::Off],
+            );
+        }
+
+        #[test]
+        fn lists_each_startup_position_once() {
+            assert_each_once(
+                &StartupPosition::all(),
+                &[
+                    StartupPosition::On,
+                    StartupPosition::Off,
+                    StartupPosition::Stay,
+                ],
+            );
+        }
+    }
+
+    mod config_fingerprint {
+        use super::*;
+        use rstest::rstest;
+
+        fn raw_info() -> RawInfo {
+            RawInfo {
+                switches: (0..=3)
+                    .map(|outlet| Switch {
+                        switch: SwitchPosition::Off,
+                        outlet,
+                    })
+                    .collect(),
+                configure: (0..=3)
+                    .map(|outlet| Startup {
+                        startup: StartupPosition::Off,
+                        outlet,
+                    })
+                    .collect(),
+                pulses: None,
+                pulse_width: None,
+                fw_version: Some("1.4.3".to_string()),
+                device_id: None,
+                sta_mac: None,
+                rssi: Some(-34),
+                bssid: None,
+                ssid: None,
+                free_heap: None,
+                fault_count: None,
+                last_error: None,
+                ip_mode: None,
+                sled_brightness: Some(30),
+                power_watts: None,
+                outlet_names: None,
+                button_press_count: None,
+                last_press_time: None,
+                pairing: None,
+                wifi_security: None,
+                reset_reason: None,
+                ntp_server: None,
+                sled_online: None,
+            }
+        }
+
+        fn info() -> Info {
+            Info {
+                sled_brightness: Some(30),
+                ssid: Some("home".to_string()),
+                ..test_support::info()
+            }
+        }
+
+        #[test]
+        fn equal_for_identical_configs() {
+            let mut other = raw_info();
+            other.switches[0].switch = SwitchPosition::On;
+            other.rssi = Some(-70);
+            other.configure.reverse();
+            let mut other_info = info();
+            other_info.switch = SwitchPosition::On;
+            other_info.fw_version = "1.4.4".to_string();
+
+            assert_eq!(raw_info().config_fingerprint(), other.config_fingerprint());
+            assert_eq!(info().config_fingerprint(), other_info.config_fingerprint());
+        }
+
+        #[rstest]
+        #[case::startup(|v: &mut RawInfo| v.configure[2].startup = StartupPosition::Stay)]
+        #[case::brightness(|v: &mut RawInfo| v.sled_brightness = Some(31))]
+        #[case::led(|v: &mut RawInfo| v.sled_online = Some(false))]
+        #[case::pulse(|v: &mut RawInfo| v.pulses = Some(vec![Pulse {
+            pulse: PulsePosition::On,
+            switch: SwitchPosition::Off,
+            outlet: 0,
+            width: PulseWidth(1500),
+        }]))]
+        #[case::ssid(|v: &mut RawInfo| v.ssid = Some("home".to_string()))]
+        fn differs_for_changed_configs(#[case] change: fn(&mut RawInfo)) {
+            let mut changed = raw_info();
+            change(&mut changed);
+
+            assert_ne!(
+                raw_info().config_fingerprint(),
+                changed.config_fingerprint()
+            )
+        }
+
+        #[rstest]
+        #[case::startup(|v: &mut Info| v.startup = StartupPosition::Stay)]
+        #[case::pulse_position(|v: &mut Info| v.pulse.as_mut().unwrap().pulse = PulsePosition::On)]
+        #[case::pulse_width(|v: &mut Info| v.pulse.as_mut().unwrap().width = PulseWidth(1500))]
+        #[case::pulse_missing(|v: &mut Info| v.pulse = None)]
+        #[case::brightness(|v: &mut Info| v.sled_brightness = Some(31))]
+        #[case::led(|v: &mut Info| v.status_led = false)]
+        #[case::ssid(|v: &mut Info| v.ssid = Some("office".to_string()))]
+        fn differs_for_changed_info_configs(#[case] change: fn(&mut Info)) {
+            let mut changed = info();
+            change(&mut changed);
+
+            assert_ne!(info().config_fingerprint(), changed.config_fingerprint())
+        }
+
+        #[test]
+        fn stable_between_runs() {
+            assert_eq!(raw_info().config_fingerprint(), 0x6d0448c769fbaedb)
+        }
+    }
+
+    mod switch_position_conversions {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::on(true, SwitchPosition::On)]
+        #[case::off(false, SwitchPosition::Off)]
+        fn converted_from_bool(#[case] value: bool, #[case] expected: SwitchPosition) {
+            assert_eq!(SwitchPosition::from(value), expected)
+        }
+
+        #[rstest]
+        #[case::on("on", Ok(SwitchPosition::On))]
+        #[case::off("off", Ok(SwitchPosition::Off))]
+        #[case::unknown(
+            "unavailable",
+            Err(Error::InvalidParameter("unknown switch position unavailable".to_string()))
+        )]
+        fn converted_from_str(
+            #[case] value: &str,
+            #[case] expected: Result<SwitchPosition, Error>,
+        ) {
+            assert_eq!(SwitchPosition::try_from(value), expected)
+        }
+
+        #[rstest]
+        #[case::on("on", Ok(SwitchPosition::On))]
+        #[case::off("off", Ok(SwitchPosition::Off))]
+        #[case::uppercase("ON", Ok(SwitchPosition::On))]
+        #[case::mixed_case("Off", Ok(SwitchPosition::Off))]
+        #[case::unknown(
+            "Unavailable",
+            Err(Error::InvalidParameter("unknown switch position Unavailable".to_string()))
+        )]
+        fn parsed(#[case] value: &str, #[case] expected: Result<SwitchPosition, Error>) {
+            assert_eq!(value.parse::<SwitchPosition>(), expected)
+        }
+
+        #[rstest]
+        #[case::on(SwitchPosition::On, "on")]
+        #[case::off(SwitchPosition::Off, "off")]
+        #[case::unknown(SwitchPosition::Unknown("unavailable".to_string()), "unavailable")]
+        fn displayed(#[case] position: SwitchPosition, #[case] expected: &str) {
+            assert_eq!(position.to_string(), expected)
+        }
+    }
+
+    mod startup_position_strings {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::on("on", Ok(StartupPosition::On))]
+        #[case::off("OFF", Ok(StartupPosition::Off))]
+        #[case::stay("Stay", Ok(StartupPosition::Stay))]
+        #[case::unknown(
+            "last",
+            Err(Error::InvalidParameter("unknown startup position last".to_string()))
+        )]
+        fn parsed(#[case] value: &str, #[case] expected: Result<StartupPosition, Error>) {
+            assert_eq!(value.parse::<StartupPosition>(), expected)
+        }
+
+        #[rstest]
+        #[case::on(StartupPosition::On, "on")]
+        #[case::off(StartupPosition::Off, "off")]
+        #[case::stay(StartupPosition::Stay, "stay")]
+        fn displayed(#[case] position: StartupPosition, #[case] expected: &str) {
+            assert_eq!(position.to_string(), expected)
+        }
+    }
+
+    mod startup_position_from_switch_position {
+        use super::*;
+
+        #[test]
+        fn converts_on() {
+            assert_eq!(
+                StartupPosition::from(SwitchPosition::On),
+                StartupPosition::On
+            )
+        }
+
+        #[test]
+        fn converts_off() {
+            assert_eq!(
+                StartupPosition::from(SwitchPosition::Off),
+                StartupPosition::Off
+            )
+        }
+
+        #[test]
+        fn converts_unknown_to_off() {
+            assert_eq!(
+                StartupPosition::from(SwitchPosition::Unknown("unavailable".to_string())),
+                StartupPosition::Off
+            )
+        }
+    }
+
+    mod error_code {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::integer(r#"{"error": 0}"#)]
+        #[case::string(r#"{"error": "0"}"#)]
+        #[case::integral_float(r#"{"error": 0.0}"#)]
+        fn accepts_expected_forms(#[case] body: &str) {
+            let got: EmptyResponse = serde_json::from_str(body).unwrap();
+
+            assert_eq!(got.error, 0)
+        }
+
+        #[rstest]
+        #[case::fractional_float(r#"{"error": 0.5}"#)]
+        #[case::negative_float(r#"{"error": -1.0}"#)]
+        #[case::not_a_number(r#"{"error": "zero"}"#)]
+        fn rejects_invalid_forms(#[case] body: &str) {
+            let got = serde_json::from_str::<EmptyResponse>(body);
+
+            assert!(got.is_err())
+        }
+    }
+
+    mod error_message {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::msg(r#"{"error": 400, "msg": "data.switches outlet invalid"}"#)]
+        #[case::message(r#"{"error": 400, "message": "data.switches outlet invalid"}"#)]
+        fn returns_expected_result(#[case] body: &str) {
+            let got: EmptyResponse = serde_json::from_str(body).unwrap();
+
+            assert_eq!(
+                <()>::try_from(got).unwrap_err(),
+                Error::WrongParameters(Some("data.switches outlet invalid".to_string()))
+            )
+        }
+
+        #[test]
+        fn displayed_with_message() {
+            let got = Error::WrongParameters(Some("data.switches outlet invalid".to_string()))
+                .to_string();
+
+            assert_eq!(
+                got,
+                "API errored with code 400: data.switches outlet invalid"
+            )
+        }
+    }
+
+    mod info_serde {
+        use super::*;
+
+        fn info() -> Info {
+            Info {
+                switch: SwitchPosition::On,
+                startup: StartupPosition::Stay,
+                status_led: false,
+                ..test_support::info()
+            }
+        }
+
+        #[test]
+        fn serialized_with_stable_names() {
+            let got = serde_json::to_value(info()).unwrap();
+
+            assert_eq!(
+                got,
+                serde_json::json!({
+                    "switch": "on",
+                    "startup": "stay",
+                    "status_led": false,
+                    "device_id": "1000abcdef",
+                    "fw_version": "1.4.3",
+                    "pulse": {"pulse": "off", "switch": "off", "outlet": 0, "width": 0},
+                    "sled_brightness": null,
+                    "ssid": null,
+                })
+            )
+        }
+
+        #[test]
+        fn round_tripped() {
+            let serialized = serde_json::to_string(&info()).unwrap();
+
+            let got: Info = serde_json::from_str(&serialized).unwrap();
+
+            assert_eq!(got, info())
+        }
+    }
+
+    mod rssi {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::weakest(-100, 0)]
+        #[case::weaker_than_weakest(-120, 0)]
+        #[case::medium(-67, 66)]
+        #[case::strongest(-50, 100)]
+        #[case::stronger_than_strongest(-30, 100)]
+        fn returns_expected_quality(#[case] dbm: i32, #[case] expected: u8) {
+            assert_eq!(Rssi(dbm).quality_percent(), expected)
+        }
+
+        #[test]
+        fn displayed_with_unit() {
+            assert_eq!(Rssi(-67).to_string(), "-67 dBm")
+        }
+
+        #[test]
+        fn returns_dbm() {
+            assert_eq!(Rssi(-67).dbm(), -67)
+        }
+    }
+
+    mod parse_json {
+        use super::*;
+
+        #[test]
+        fn errored_with_body_beginning() {
+            let body = format!(
+                "<html><title>Router login</title>{}</html>",
+                "x".repeat(500)
+            );
+
+            let got = parse_json::<EmptyResponse>(body.as_bytes());
+
+            assert_eq!(
+                got.err().unwrap(),
+                Error::MalformedResponse {
+                    message: "expected value at line 1 column 1".to_string(),
+                    body: body[..BODY_SNIPPET_LEN].to_string(),
+                }
+            )
+        }
+
+        #[test]
+        fn errored_on_truncated_body() {
+            let got = parse_json::<EmptyResponse>(br#"{"error": 0, "da"#);
+
+            assert_eq!(
+                got.err().unwrap().to_string(),
+                "Device responded with malformed body (EOF while parsing a string at line 1 \
+                 column 16): {\"error\": 0, \"da"
+            )
+        }
+    }
+
+    mod from_api_error_code {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::wrong_parameters(400, Error::WrongParameters(None))]
+        #[case::unauthorized(401, Error::Unauthorized(None))]
+        #[case::unsupported_endpoint(404, Error::UnsupportedEndpoint(None))]
+        #[case::invalid_request_parameters(422, Error::InvalidRequestParameters(None))]
+        #[case::unknown(503, Error::Unknown(503, None))]
+        fn returns_expected_result(#[case] code: usize, #[case] expected: Error) {
+            let got = Error::from_api_error_code(code, None);
+
+            assert_eq!(got, expected)
+        }
+
+        #[rstest]
+        #[case::unauthorized(401, "API errored with code 401, request is unauthorized")]
+        #[case::unsupported_endpoint(404, "API errored with code 404, endpoint is unsupported")]
+        #[case::invalid_request_parameters(
+            422,
+            "API errored with code 422, invalid request parameters"
+        )]
+        #[case::unknown(503, "API errored with unknown code 503")]
+        fn displayed_with_code(#[case] code: usize, #[case] expected: &str) {
+            let got = Error::from_api_error_code(code, None).to_string();
+
+            assert_eq!(got, expected)
+        }
+    }
+
+    mod pulse_width {
+        use super::*;
+        use rstest::rstest;
+
+        #[rstest]
+        #[case::integer("1500", PulseWidth(1500))]
+        #[case::string(r#""1500""#, PulseWidth(1500))]
+        fn accepts_expected_forms(#[case] body: &str, #[case] expected: PulseWidth) {
+            let got: PulseWidth = serde_json::from_str(body).unwrap();
+
+            assert_eq!(got, expected)
+        }
+
+        #[rstest]
+        #[case::negative("-1")]
+        #[case::fractional("1.5")]
+        #[case::not_a_number(r#""long""#)]
+        fn rejects_invalid_forms(#[case] body: &str) {
+            let got = serde_json::from_str::<PulseWidth>(body);
+
+            assert!(got.is_err())
+        }
+
+        #[rstest]
+        #[case::milliseconds(PulseWidthUnit::Milliseconds, Duration::from_millis(2))]
+        #[case::seconds(PulseWidthUnit::Seconds, Duration::from_secs(2))]
+        fn converted_in_given_unit(#[case] unit: PulseWidthUnit, #[case] expected: Duration) {
+            assert_eq!(PulseWidth(2).as_duration(unit), expected)
+        }
+    }
+
+    mod switches_request {
+        use super::*;
+
+        #[test]
+        fn static_body_is_identical_to_serialized() {
+            for position in [
+                SwitchPosition::On,
+                SwitchPosition::Off,
+                SwitchPosition::Unknown("unavailable".to_string()),
+            ] {
+                assert_eq!(
+                    SwitchesRequest::body(position.clone()).as_ref(),
+                    SwitchesRequest::serialize(position).as_slice()
+                )
+            }
         }
     }
 }
@@ -0,0 +1,45 @@
+use crate::models::Error;
+use crate::Result;
+
+/// Returns SSID of access point broadcasted by device in DIY pairing mode.
+///
+/// Freshly reset device broadcasts AP named `ITEAD-<device id>`, where device id is 10 hex
+/// digits. `Error::InvalidDeviceId` is returned for device id of other format.
+///
+/// # Example
+///
+/// ```
+/// # use sonoff_minir3::expected_ap_ssid;
+///
+/// assert_eq!(expected_ap_ssid("1000abcdef").unwrap(), "ITEAD-1000abcdef");
+/// ```
+pub fn expected_ap_ssid(device_id: &str) -> Result<String> {
+    if device_id.len() != 10 || !device_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::InvalidDeviceId(device_id.to_string()));
+    }
+    Ok(format!("ITEAD-{}", device_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod expected_ap_ssid {
+        use super::*;
+
+        #[test]
+        fn returns_expected_result() {
+            assert_eq!(expected_ap_ssid("1000abcdef").unwrap(), "ITEAD-1000abcdef")
+        }
+
+        #[test]
+        fn errored_on_invalid_device_id() {
+            for device_id in ["", "1000abcde", "1000abcdef0", "1000abcdeg"] {
+                assert_eq!(
+                    expected_ap_ssid(device_id),
+                    Err(Error::InvalidDeviceId(device_id.to_string()))
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+#[cfg(feature = "tokio")]
+use crate::tasks::Tasks;
+#[cfg(feature = "tokio")]
+use std::sync::Arc;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+#[cfg(feature = "tokio")]
+use tokio_util::sync::DropGuard;
+
+type Factory = Box<dyn Fn() -> reqwest::Result<reqwest::Client> + Send + Sync>;
+
+/// HTTP client shared between `Client` clones and the reaper.
+///
+/// Reaping means replacing the underlying `reqwest::Client` with a fresh one, so all idle
+/// connections of the previous one are closed once it's dropped.
+pub(crate) struct Pool {
+    client: RwLock<reqwest::Client>,
+    last_used: Mutex<Option<Instant>>,
+    factory: Factory,
+}
+
+impl Pool {
+    pub(crate) fn new(factory: Factory) -> reqwest::Result<Self> {
+        Ok(Pool {
+            client: RwLock::new(factory()?),
+            last_used: Mutex::new(None),
+            factory,
+        })
+    }
+
+    pub(crate) fn get(&self) -> reqwest::Client {
+        *self.last_used.lock().unwrap() = Some(Instant::now());
+        self.client.read().unwrap().clone()
+    }
+
+    #[cfg_attr(not(feature = "tokio"), allow(dead_code))]
+    fn reap(&self, max_idle: Duration) {
+        let mut last_used = self.last_used.lock().unwrap();
+        match *last_used {
+            Some(v) if v.elapsed() >= max_idle => {}
+            _ => return,
+        }
+        if let Ok(client) = (self.factory)() {
+            *self.client.write().unwrap() = client;
+            *last_used = None;
+        }
+    }
+}
+
+/// Background task periodically reaping idle connections of the `Pool`.
+///
+/// Task is stopped when reaper is dropped or client is closed.
+#[cfg(feature = "tokio")]
+pub(crate) struct Reaper {
+    // Only held to stop task once reaper is dropped
+    _task: DropGuard,
+}
+
+#[cfg(feature = "tokio")]
+impl Reaper {
+    pub(crate) fn spawn(
+        tasks: &Tasks,
+        pool: Arc<Pool>,
+        max_idle: Duration,
+    ) -> std::io::Result<Self> {
+        let task = tasks.spawn(async move {
+            let mut interval = tokio::time::interval(max_idle);
+            loop {
+                interval.tick().await;
+                pool.reap(max_idle);
+            }
+        })?;
+        Ok(Reaper { _task: task })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_pool() -> Pool {
+        Pool::new(Box::new(|| reqwest::Client::builder().build())).unwrap()
+    }
+
+    mod reap {
+        use super::*;
+
+        #[test]
+        fn reaped_if_idle_for_too_long() {
+            let pool = make_pool();
+            pool.get();
+
+            pool.reap(Duration::ZERO);
+
+            assert!(pool.last_used.lock().unwrap().is_none());
+        }
+
+        #[test]
+        fn skipped_if_recently_used() {
+            let pool = make_pool();
+            pool.get();
+
+            pool.reap(Duration::from_secs(60));
+
+            assert!(pool.last_used.lock().unwrap().is_some());
+        }
+    }
+}
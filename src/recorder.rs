@@ -0,0 +1,49 @@
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Appends request/response pairs to JSONL file, look at `ClientBuilder::record_to`.
+pub(crate) struct Recorder {
+    file: Mutex<File>,
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    endpoint: &'a str,
+    request: &'a str,
+    response: &'a str,
+    // Milliseconds since unix epoch
+    timestamp: u128,
+}
+
+impl Recorder {
+    pub(crate) fn open(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Recorder {
+            file: Mutex::new(file),
+        })
+    }
+
+    pub(crate) fn record(
+        &self,
+        endpoint: &str,
+        request: &[u8],
+        response: &[u8],
+    ) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(&Line {
+            endpoint,
+            request: &String::from_utf8_lossy(request),
+            response: &String::from_utf8_lossy(response),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis(),
+        })?;
+        line.push(b'\n');
+        // Whole line is written at once, so lines of concurrent requests don't mix
+        self.file.lock().unwrap().write_all(&line)
+    }
+}
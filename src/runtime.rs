@@ -0,0 +1,80 @@
+//! Async runtime primitives used by client.
+//!
+//! Tokio is used with `tokio` feature (enabled by default), async-std is used with `async-std`
+//! feature. If both are enabled, tokio takes precedence.
+
+#[cfg(not(any(feature = "tokio", feature = "async-std")))]
+compile_error!("either `tokio` or `async-std` feature must be enabled");
+
+#[cfg(feature = "tokio")]
+pub(crate) use tokio_impl::*;
+
+#[cfg(all(feature = "async-std", not(feature = "tokio")))]
+pub(crate) use async_std_impl::*;
+
+#[cfg(feature = "tokio")]
+mod tokio_impl {
+    use std::future::Future;
+    use std::io;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    /// Fair mutex, waiters acquire it in order they started waiting.
+    pub(crate) use tokio::sync::Mutex;
+
+    pub(crate) async fn sleep(duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+
+    /// Returns `None` if future didn't complete within given duration.
+    pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+        tokio::time::timeout(duration, future).await.ok()
+    }
+
+    pub(crate) async fn lookup_host(host: String) -> io::Result<Vec<SocketAddr>> {
+        Ok(tokio::net::lookup_host(host).await?.collect())
+    }
+
+    #[cfg(feature = "discovery")]
+    pub(crate) async fn spawn_blocking<T, F>(f: F) -> io::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        Ok(tokio::task::spawn_blocking(f).await?)
+    }
+}
+
+#[cfg(all(feature = "async-std", not(feature = "tokio")))]
+mod async_std_impl {
+    use async_std::net::ToSocketAddrs;
+    use std::future::Future;
+    use std::io;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    /// Unlike tokio one, this mutex doesn't guarantee that waiters acquire it in order.
+    pub(crate) use async_std::sync::Mutex;
+
+    pub(crate) async fn sleep(duration: Duration) {
+        async_std::task::sleep(duration).await
+    }
+
+    /// Returns `None` if future didn't complete within given duration.
+    pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+        async_std::future::timeout(duration, future).await.ok()
+    }
+
+    pub(crate) async fn lookup_host(host: String) -> io::Result<Vec<SocketAddr>> {
+        Ok(host.to_socket_addrs().await?.collect())
+    }
+
+    #[cfg(feature = "discovery")]
+    pub(crate) async fn spawn_blocking<T, F>(f: F) -> io::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        Ok(async_std::task::spawn_blocking(f).await)
+    }
+}
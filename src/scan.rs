@@ -0,0 +1,159 @@
+use crate::client::{http_client, with_budget, ClientBuilder};
+use crate::models::{Error, Info};
+use crate::Result;
+use futures_util::stream::{self, StreamExt};
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
+/// Probe info endpoint of every host within IPv4 `cidr`, e.g. `"192.168.1.0/24"`.
+///
+/// It's useful to find devices on networks where mDNS is blocked. Up to `concurrency` hosts are
+/// probed at once sharing one HTTP client, each probe should fit into `timeout`, otherwise
+/// `Error::BudgetExceeded` is returned for it. Results are ordered by address, network and broadcast addresses aren't
+/// probed. Malformed `cidr` is rejected with `Error::InvalidParameter`.
+///
+/// # Example
+///
+/// ```ignore
+/// let got = scan_cidr("192.168.1.0/24", 8081, 32, Duration::from_secs(1)).await?;
+///
+/// for (addr, info) in got.into_iter().filter(|(_, v)| v.is_ok()) {
+///     println!("{}: {:?}", addr, info.unwrap());
+/// }
+/// ```
+pub async fn scan_cidr(
+    cidr: &str,
+    port: u16,
+    concurrency: usize,
+    timeout: Duration,
+) -> Result<Vec<(IpAddr, Result<Info>)>> {
+    let hosts = cidr_hosts(cidr)?;
+    let http = http_client(None, None)?;
+    Ok(stream::iter(hosts)
+        .map(|addr| {
+            let http = http.clone();
+            async move {
+                let got = with_budget(timeout, async {
+                    ClientBuilder::new(addr.to_string(), port)
+                        .http_client(http)
+                        .build()?
+                        .fetch_info()
+                        .await
+                })
+                .await;
+                (addr, got)
+            }
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await)
+}
+
+fn cidr_hosts(cidr: &str) -> Result<impl Iterator<Item = IpAddr>> {
+    let invalid = || Error::InvalidParameter(format!("invalid IPv4 CIDR {}", cidr));
+
+    let (addr, prefix) = cidr.split_once('/').ok_or_else(invalid)?;
+    let addr: Ipv4Addr = addr.parse().map_err(|_| invalid())?;
+    let prefix: u32 = prefix.parse().map_err(|_| invalid())?;
+    if prefix > 32 {
+        return Err(invalid());
+    }
+
+    let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+    let network = u32::from(addr) & mask;
+    let broadcast = network | !mask;
+    // /31 and /32 have no network and broadcast addresses
+    let range = if prefix >= 31 {
+        network..=broadcast
+    } else {
+        network + 1..=broadcast - 1
+    };
+    Ok(range.map(|v| IpAddr::V4(Ipv4Addr::from(v))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use rstest::rstest;
+
+    mod cidr_hosts {
+        use super::*;
+
+        #[rstest]
+        #[case::subnet("192.168.1.0/30", &["192.168.1.1", "192.168.1.2"])]
+        #[case::not_network_address("192.168.1.5/30", &["192.168.1.5", "192.168.1.6"])]
+        #[case::point_to_point("10.0.0.0/31", &["10.0.0.0", "10.0.0.1"])]
+        #[case::single("10.0.0.7/32", &["10.0.0.7"])]
+        fn returns_expected_result(#[case] cidr: &str, #[case] expected: &[&str]) {
+            let expected: Vec<IpAddr> = expected.iter().map(|v| v.parse().unwrap()).collect();
+
+            assert_eq!(cidr_hosts(cidr).unwrap().collect::<Vec<_>>(), expected);
+        }
+
+        #[rstest]
+        #[case::no_prefix("192.168.1.0")]
+        #[case::prefix_too_long("192.168.1.0/33")]
+        #[case::ipv6("fe80::/64")]
+        #[case::garbage("not a cidr/24")]
+        fn errored_in_expected_way(#[case] cidr: &str) {
+            assert_eq!(
+                cidr_hosts(cidr).err(),
+                Some(Error::InvalidParameter(format!(
+                    "invalid IPv4 CIDR {}",
+                    cidr
+                )))
+            );
+        }
+    }
+
+    mod scan_cidr {
+        use super::*;
+        use tokio::io::AsyncWriteExt;
+        use tokio::net::TcpListener;
+
+        // Serves info fixture to every request, httpmock can't bind to specific address
+        async fn serve_info(listener: TcpListener) {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                read_request(&mut stream).await;
+
+                let body = load_fixture("response_info_ok.json");
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\n\
+                     Content-Type: application/json; charset=utf-8\r\n\
+                     Content-Length: {}\r\n\
+                     Connection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        }
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let listener = TcpListener::bind("127.0.0.2:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let other = TcpListener::bind(("127.0.0.5", port)).await.unwrap();
+            tokio::spawn(serve_info(listener));
+            tokio::spawn(serve_info(other));
+
+            let got = scan_cidr("127.0.0.0/29", port, 4, Duration::from_secs(5))
+                .await
+                .unwrap();
+
+            let addrs: Vec<IpAddr> = got.iter().map(|(v, _)| *v).collect();
+            let expected: Vec<IpAddr> = (1..=6)
+                .map(|v| IpAddr::V4(Ipv4Addr::new(127, 0, 0, v)))
+                .collect();
+            assert_eq!(addrs, expected);
+            for (addr, result) in got {
+                match addr.to_string().as_str() {
+                    "127.0.0.2" | "127.0.0.5" => assert_eq!(result.unwrap(), info()),
+                    _ => assert!(result.is_err()),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+#[cfg(feature = "wifi")]
+use crate::WifiSetResult;
+use crate::{Client, Error, Info, RawInfo, Result, StartupPosition, SwitchPosition};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Represents command that could be sent via `ClientService`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceCommand {
+    FetchInfo,
+    FetchRawInfo,
+    SetStartupPosition(StartupPosition),
+    SetSwitchPosition(SwitchPosition),
+    #[cfg(feature = "wifi")]
+    SetWifi {
+        ssid: String,
+        password: String,
+    },
+}
+
+/// Represents result of `DeviceCommand`, variants match command ones.
+///
+/// `Done` is returned for commands that don't return anything.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceResponse {
+    Info(Info),
+    RawInfo(Box<RawInfo>),
+    Done,
+    #[cfg(feature = "wifi")]
+    WifiSet(WifiSetResult),
+}
+
+/// Adapter exposing `Client` as Tower `Service`, so it could be used with generic Tower
+/// middleware.
+///
+/// # Example
+///
+/// ```ignore
+/// let service = ServiceBuilder::new()
+///     .concurrency_limit(1)
+///     .service(ClientService::new(client));
+///
+/// let got = service.oneshot(DeviceCommand::FetchInfo).await;
+/// ```
+#[derive(Clone)]
+pub struct ClientService {
+    client: Client,
+}
+
+impl ClientService {
+    pub fn new(client: Client) -> Self {
+        ClientService { client }
+    }
+}
+
+impl tower::Service<DeviceCommand> for ClientService {
+    type Response = DeviceResponse;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<DeviceResponse>> + Send>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, command: DeviceCommand) -> Self::Future {
+        let client = self.client.clone();
+        Box::pin(async move {
+            Ok(match command {
+                DeviceCommand::FetchInfo => DeviceResponse::Info(client.fetch_info().await?),
+                DeviceCommand::FetchRawInfo => {
+                    DeviceResponse::RawInfo(Box::new(client.fetch_raw_info().await?))
+                }
+                DeviceCommand::SetStartupPosition(v) => {
+                    client.set_startup_position(v).await?;
+                    DeviceResponse::Done
+                }
+                DeviceCommand::SetSwitchPosition(v) => {
+                    client.set_switch_position(v).await?;
+                    DeviceResponse::Done
+                }
+                #[cfg(feature = "wifi")]
+                DeviceCommand::SetWifi { ssid, password } => {
+                    DeviceResponse::WifiSet(client.set_wifi(&ssid, &password).await?)
+                }
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use tower::{ServiceBuilder, ServiceExt};
+
+    mod client_service {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_expected_result() {
+            let (_server, client) = mock_client_returning("info", "response_info_ok.json");
+            let service = ServiceBuilder::new()
+                .concurrency_limit(1)
+                .service(ClientService::new(client));
+
+            let got = service.oneshot(DeviceCommand::FetchInfo).await;
+
+            assert_eq!(got.unwrap(), DeviceResponse::Info(info()))
+        }
+
+        #[tokio::test]
+        async fn returns_done_for_void_commands() {
+            let (_server, client) = mock_client_returning("switches", "response_ok.json");
+            let service = ServiceBuilder::new()
+                .concurrency_limit(1)
+                .service(ClientService::new(client));
+
+            let got = service
+                .oneshot(DeviceCommand::SetSwitchPosition(SwitchPosition::On))
+                .await;
+
+            assert_eq!(got.unwrap(), DeviceResponse::Done)
+        }
+    }
+}
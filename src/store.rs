@@ -0,0 +1,162 @@
+use crate::models::Info;
+use crate::{Client, Result};
+use std::time::{Duration, SystemTime};
+
+/// Info stored along with time it was fetched at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedInfo {
+    pub info: Info,
+    pub fetched_at: SystemTime,
+}
+
+/// User supplied storage for device info, look at `Client::fetch_info_cached` for details.
+///
+/// It allows to keep info across process restarts, e.g. in a file or database.
+pub trait InfoStore {
+    /// Returns stored info, `None` if nothing is stored yet
+    fn load(&self) -> Result<Option<CachedInfo>>;
+
+    /// Replaces stored info with given one
+    fn save(&self, cached: &CachedInfo) -> Result<()>;
+}
+
+impl Client {
+    /// Fetch device info, consulting `store` first.
+    ///
+    /// Stored info is returned if it was fetched less than `ttl` ago, otherwise info is fetched
+    /// from device and saved to `store`. Store errors are returned as is.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let got = client
+    ///     .fetch_info_cached(&store, Duration::from_secs(60))
+    ///     .await;
+    ///
+    /// assert!(got.is_ok());
+    /// ```
+    pub async fn fetch_info_cached(&self, store: &impl InfoStore, ttl: Duration) -> Result<Info> {
+        if let Some(cached) = store.load()? {
+            // Time going backwards is treated as stale info
+            let fresh = cached
+                .fetched_at
+                .elapsed()
+                .map(|v| v < ttl)
+                .unwrap_or(false);
+            if fresh {
+                return Ok(cached.info);
+            }
+        }
+
+        let info = self.fetch_info().await?;
+        store.save(&CachedInfo {
+            info: info.clone(),
+            fetched_at: SystemTime::now(),
+        })?;
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{StartupPosition, SwitchPosition};
+    use crate::test_support::*;
+    use httpmock::MockServer;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryStore {
+        cached: Mutex<Option<CachedInfo>>,
+    }
+
+    impl InfoStore for MemoryStore {
+        fn load(&self) -> Result<Option<CachedInfo>> {
+            Ok(self.cached.lock().unwrap().clone())
+        }
+
+        fn save(&self, cached: &CachedInfo) -> Result<()> {
+            *self.cached.lock().unwrap() = Some(cached.clone());
+            Ok(())
+        }
+    }
+
+    mod fetch_info_cached {
+        use super::*;
+
+        const TTL: Duration = Duration::from_secs(60);
+
+        fn stored_info() -> Info {
+            Info {
+                switch: SwitchPosition::On,
+                startup: StartupPosition::Stay,
+                ..info()
+            }
+        }
+
+        fn mock_info(server: &MockServer) -> httpmock::Mock<'_> {
+            server.mock(|when, then| {
+                when.method("POST").path("/zeroconf/info");
+                then.status(200)
+                    .header("content-type", "application/json; charset=utf-8")
+                    .body(load_fixture("response_info_ok.json"));
+            })
+        }
+
+        #[tokio::test]
+        async fn returned_stored_info_on_hit() {
+            let (server, client) = make_server_and_client();
+            let mock = mock_info(&server);
+            let store = MemoryStore::default();
+            store
+                .save(&CachedInfo {
+                    info: stored_info(),
+                    fetched_at: SystemTime::now(),
+                })
+                .unwrap();
+
+            let got = client.fetch_info_cached(&store, TTL).await;
+
+            mock.assert_hits(0);
+
+            assert_eq!(got.unwrap(), stored_info());
+        }
+
+        #[tokio::test]
+        async fn fetched_and_saved_on_miss() {
+            let (server, client) = make_server_and_client();
+            let mock = mock_info(&server);
+            let store = MemoryStore::default();
+
+            let got = client.fetch_info_cached(&store, TTL).await;
+
+            mock.assert();
+
+            assert_eq!(got.unwrap(), info());
+            assert_eq!(store.load().unwrap().unwrap().info, info());
+        }
+
+        #[tokio::test]
+        async fn fetched_and_saved_when_stale() {
+            let (server, client) = make_server_and_client();
+            let mock = mock_info(&server);
+            let store = MemoryStore::default();
+            let fetched_at = SystemTime::now() - TTL * 2;
+            store
+                .save(&CachedInfo {
+                    info: stored_info(),
+                    fetched_at,
+                })
+                .unwrap();
+
+            let got = client.fetch_info_cached(&store, TTL).await;
+
+            mock.assert();
+
+            assert_eq!(got.unwrap(), info());
+            let saved = store.load().unwrap().unwrap();
+            assert_eq!(saved.info, info());
+            assert!(saved.fetched_at > fetched_at);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::future::Future;
+use std::io;
+use tokio_util::sync::{CancellationToken, DropGuard};
+use tokio_util::task::TaskTracker;
+
+/// Background tasks spawned by `Client` and its clones.
+///
+/// All of them are stopped by `close`, which also waits for them to finish.
+#[derive(Default)]
+pub(crate) struct Tasks {
+    token: CancellationToken,
+    tracker: TaskTracker,
+}
+
+impl Tasks {
+    /// Spawns given future on current runtime, it's dropped once task is stopped.
+    ///
+    /// Task is also stopped when returned guard is dropped. Runtime is required, so it fails
+    /// outside of it.
+    pub(crate) fn spawn<F>(&self, future: F) -> io::Result<DropGuard>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let runtime = tokio::runtime::Handle::try_current().map_err(io::Error::other)?;
+        let token = self.token.child_token();
+        self.tracker.spawn_on(
+            {
+                let token = token.clone();
+                async move {
+                    token.run_until_cancelled(future).await;
+                }
+            },
+            &runtime,
+        );
+        Ok(token.drop_guard())
+    }
+
+    /// Stops all tasks and waits for them to finish.
+    ///
+    /// Tasks spawned afterwards are stopped right away.
+    pub(crate) async fn close(&self) {
+        self.token.cancel();
+        self.tracker.close();
+        self.tracker.wait().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot;
+
+    // Task that never completes on its own, receiver is notified once it's dropped
+    fn pending_task() -> (impl Future<Output = ()>, oneshot::Receiver<()>) {
+        let (sender, receiver) = oneshot::channel::<()>();
+        let task = async move {
+            let _sender = sender;
+            std::future::pending().await
+        };
+        (task, receiver)
+    }
+
+    mod close {
+        use super::*;
+        use tokio::sync::oneshot::error::TryRecvError;
+
+        #[tokio::test]
+        async fn stopped_all_tasks() {
+            let tasks = Tasks::default();
+            let (first, mut first_stopped) = pending_task();
+            let (second, mut second_stopped) = pending_task();
+            let _first = tasks.spawn(first).unwrap();
+            let _second = tasks.spawn(second).unwrap();
+
+            tasks.close().await;
+
+            assert_eq!(first_stopped.try_recv(), Err(TryRecvError::Closed));
+            assert_eq!(second_stopped.try_recv(), Err(TryRecvError::Closed));
+        }
+
+        #[tokio::test]
+        async fn stopped_tasks_spawned_afterwards() {
+            let tasks = Tasks::default();
+            tasks.close().await;
+            let (task, stopped) = pending_task();
+
+            let _task = tasks.spawn(task).unwrap();
+
+            assert!(stopped.await.is_err());
+        }
+    }
+
+    mod drop {
+        use super::*;
+
+        #[tokio::test]
+        async fn stopped_with_guard() {
+            let tasks = Tasks::default();
+            let (task, stopped) = pending_task();
+            let guard = tasks.spawn(task).unwrap();
+
+            drop(guard);
+
+            assert!(stopped.await.is_err());
+        }
+    }
+
+    #[test]
+    fn errored_outside_of_runtime() {
+        let got = Tasks::default().spawn(std::future::pending());
+
+        assert!(got.is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{
+    Client, Info, OutletIndex, Pulse, PulsePosition, PulseWidth, StartupPosition, SwitchPosition,
+};
+use httpmock::MockServer;
+use tokio::io::AsyncReadExt;
+
+pub(crate) fn load_fixture(fpath: &str) -> String {
+    let read = std::fs::read_to_string(format!("./testing_fixtures/{}", fpath)).unwrap();
+    jsonxf::minimize(&read).unwrap()
+}
+
+#[cfg(feature = "ota")]
+pub(crate) const FIRMWARE_URL: &str = "http://192.168.1.10/firmware.bin";
+#[cfg(feature = "ota")]
+pub(crate) const FIRMWARE_SHA256: &str =
+    "3858f62230ac3c915f300c664312c63f5a6f5c5e6c9a7e3d3a2f7c1b1e6d4c2a";
+
+pub(crate) fn outlet(value: u8) -> OutletIndex {
+    OutletIndex::new(value).unwrap()
+}
+
+/// Info of default outlet reported in `response_info_ok.json` fixture.
+pub(crate) fn info() -> Info {
+    Info {
+        switch: SwitchPosition::Off,
+        startup: StartupPosition::Off,
+        status_led: true,
+        device_id: "1000abcdef".to_string(),
+        fw_version: "1.4.3".to_string(),
+        pulse: Some(Pulse {
+            pulse: PulsePosition::Off,
+            switch: SwitchPosition::Off,
+            outlet: 0,
+            width: PulseWidth(0),
+        }),
+        sled_brightness: None,
+        ssid: None,
+    }
+}
+
+pub(crate) fn make_server_and_client() -> (MockServer, Client) {
+    let server = MockServer::start();
+    let client = Client::new(server.host(), server.port());
+    (server, client)
+}
+
+/// Sets up server responding to any request to `/zeroconf/{endpoint}` with given fixture.
+pub(crate) fn mock_client_returning(endpoint: &str, fixture: &str) -> (MockServer, Client) {
+    let (server, client) = make_server_and_client();
+    server.mock(|when, then| {
+        when.method("POST").path(format!("/zeroconf/{}", endpoint));
+        then.status(200)
+            .header("content-type", "application/json; charset=utf-8")
+            .body(load_fixture(fixture));
+    });
+    (server, client)
+}
+
+/// Reads whole HTTP request from raw stream, for tests httpmock can't serve.
+pub(crate) async fn read_request(stream: &mut tokio::net::TcpStream) -> String {
+    let mut request = Vec::new();
+    let mut buf = [0; 4096];
+    while !is_complete(&request) {
+        let read = stream.read(&mut buf).await.unwrap();
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+    String::from_utf8(request).unwrap()
+}
+
+fn is_complete(request: &[u8]) -> bool {
+    let request = String::from_utf8_lossy(request);
+    let Some((headers, body)) = request.split_once("\r\n\r\n") else {
+        return false;
+    };
+    let length = headers
+        .lines()
+        .find_map(|v| {
+            v.to_lowercase()
+                .strip_prefix("content-length: ")?
+                .parse()
+                .ok()
+        })
+        .unwrap_or(0);
+    body.len() >= length
+}
@@ -0,0 +1,46 @@
+//! Checks that client works without tokio, when built with `async-std` feature only.
+#![cfg(all(feature = "async-std", not(feature = "tokio")))]
+
+use sonoff_minir3::{with_budget, Client, Error};
+use std::time::Duration;
+
+#[async_std::test]
+async fn with_budget_errored_if_exceeded() {
+    let got = with_budget(Duration::from_millis(10), async {
+        async_std::task::sleep(Duration::from_millis(100)).await;
+        Ok(())
+    })
+    .await;
+
+    assert_eq!(
+        got.unwrap_err(),
+        Error::BudgetExceeded(Duration::from_millis(10))
+    );
+}
+
+#[async_std::test]
+async fn resolved_addr_returns_expected_result() {
+    let client = Client::new("localhost", 8081);
+
+    let got = client.resolved_addr().await;
+
+    assert_eq!(got.unwrap().port(), 8081);
+}
+
+#[async_std::test]
+async fn fetch_info_returns_expected_result() {
+    let server = httpmock::MockServer::start_async().await;
+    server
+        .mock_async(|when, then| {
+            when.method("POST").path("/zeroconf/info");
+            then.status(200)
+                .header("content-type", "application/json; charset=utf-8")
+                .body(include_str!("../testing_fixtures/response_info_ok.json"));
+        })
+        .await;
+    let client = Client::new(server.host(), server.port());
+
+    let got = client.fetch_info().await;
+
+    assert_eq!(got.unwrap().device_id, "1000abcdef");
+}
@@ -0,0 +1,24 @@
+//! Checks that minimal client is available when built without optional features, e.g. with
+//! `--no-default-features --features tokio`.
+#![cfg(all(feature = "tokio", not(any(feature = "ota", feature = "wifi"))))]
+
+use sonoff_minir3::{Client, Info, Result, StartupPosition, SwitchPosition};
+use std::future::Future;
+
+fn assert_command<F: Future<Output = Result<T>>, T>(_: F) {}
+
+#[test]
+fn core_api_present() {
+    let client = Client::new("127.0.0.1", 8081);
+
+    assert_command::<_, Info>(client.fetch_info());
+    assert_command::<_, ()>(client.set_switch_position(SwitchPosition::On));
+    assert_command::<_, ()>(client.set_startup_position(StartupPosition::Stay));
+}
+
+#[test]
+fn core_requests_built() {
+    let (path, _) = Client::build_info_request();
+
+    assert_eq!(path, "/zeroconf/info");
+}